@@ -0,0 +1,152 @@
+/*!
+ * Measures the latency of handling single events against a simulated yabai (see `simulated_yabai`),
+ * so that no yabai process is involved. Every iteration starts from the same state and the same
+ * windows: the scenarios cover the common case where nothing has to be rearranged as well as the
+ * paths that warp windows between columns, toggle splits and rebuild the layout from scratch.
+ *
+ * Run them with `cargo bench --bench events --features simulation`.
+ */
+
+use std::{
+	fs,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use rusty_yabai_master_stack_plugin::{
+	context::{
+		state::get_state_path,
+		window::{create_windows_manager, UpdateWindowsProps},
+		YabaiPlugin,
+	},
+	run_commands::{focus_down_window, increase_master_window_count, rebuild_layout},
+	simulated_yabai::{create_simulated_plugin, Node, SimulatedYabai},
+	state_commands::state_reset,
+	trigger_commands::{window_created, window_moved},
+};
+
+const NUM_ITERATIONS: u32 = 50;
+
+struct Scenario {
+	name: &'static str,
+	/// The window that yabai passes to the signal in `YABAI_WINDOW_ID`
+	window_id: usize,
+	/// Returns the windows that the event is handled for
+	setup: fn() -> SimulatedYabai,
+	handle_event: fn(&YabaiPlugin),
+}
+
+fn update_windows(plugin: &YabaiPlugin) {
+	let mut wm = create_windows_manager(plugin);
+	wm.update_windows(UpdateWindowsProps {
+		target_num_master_windows: 1,
+	});
+}
+
+const SCENARIOS: &[Scenario] = &[
+	// A valid layout, which only has to be checked
+	Scenario {
+		name: "focus_down_window",
+		window_id: 1,
+		setup: SimulatedYabai::new,
+		handle_event: focus_down_window,
+	},
+	// The user swapped the master window with a stack window, so the new order is adopted as is
+	Scenario {
+		name: "window_moved",
+		window_id: 3,
+		setup: || SimulatedYabai::with_columns(&[2, 1, 4, 5], &[3]),
+		handle_event: window_moved,
+	},
+	// yabai tiles the new window below the focused master window, so it has to be warped to the stack
+	Scenario {
+		name: "window_created",
+		window_id: 6,
+		setup: || {
+			let mut yabai = SimulatedYabai::new();
+			yabai.open_window(6);
+			yabai
+		},
+		handle_event: window_created,
+	},
+	// The top stack window is warped to the master column
+	Scenario {
+		name: "increase_master",
+		window_id: 1,
+		setup: SimulatedYabai::new,
+		handle_event: increase_master_window_count,
+	},
+	// All windows side by side, so their splits have to be toggled into columns
+	Scenario {
+		name: "update_windows_row",
+		window_id: 1,
+		setup: || {
+			let mut yabai = SimulatedYabai::with_tree(Some(Node::row(&[1, 2, 3, 4, 5])), 1);
+			yabai.balance();
+			yabai
+		},
+		handle_event: update_windows,
+	},
+	// Floats every window but one and tiles them again at insertion points
+	Scenario {
+		name: "rebuild_layout",
+		window_id: 1,
+		setup: SimulatedYabai::new,
+		handle_event: rebuild_layout,
+	},
+];
+
+fn run_scenario(
+	scenario: &Scenario,
+	initial_state: &str,
+	yabai: &Arc<Mutex<SimulatedYabai>>,
+	plugin: &YabaiPlugin,
+) {
+	std::env::set_var("YABAI_WINDOW_ID", scenario.window_id.to_string());
+
+	let mut total_time = Duration::ZERO;
+	let mut num_commands = 0;
+	let mut num_queries = 0;
+	for _ in 0..NUM_ITERATIONS {
+		fs::write(get_state_path(), initial_state).expect("Failed to restore the state");
+		*yabai.lock().unwrap() = (scenario.setup)();
+
+		let start = Instant::now();
+		(scenario.handle_event)(plugin);
+		total_time += start.elapsed();
+
+		let yabai = yabai.lock().unwrap();
+		num_commands += yabai.num_commands;
+		num_queries += yabai.num_queries;
+	}
+
+	println!(
+		"{:<20} {:>10.3} ms {:>10.1} {:>10.1}",
+		scenario.name,
+		total_time.as_secs_f64() * 1000.0 / NUM_ITERATIONS as f64,
+		num_commands as f64 / NUM_ITERATIONS as f64,
+		num_queries as f64 / NUM_ITERATIONS as f64
+	);
+}
+
+fn main() {
+	// The benchmarks run in a process of their own, so the plugin directory only changes for them
+	let plugin_dir = std::env::temp_dir().join(format!("yabai-benchmark-{}", std::process::id()));
+	fs::create_dir_all(&plugin_dir).expect("Failed to create the plugin directory");
+	std::env::set_var("MASTER_STACK_PLUGIN_DIR", &plugin_dir);
+
+	let yabai = Arc::new(Mutex::new(SimulatedYabai::new()));
+	let plugin = create_simulated_plugin(yabai.clone());
+	state_reset(&plugin, &[]);
+	let initial_state = fs::read_to_string(get_state_path()).expect("Failed to read the state");
+
+	println!(
+		"{:<20} {:>13} {:>10} {:>10}",
+		"event", "wall time", "commands", "queries"
+	);
+	for scenario in SCENARIOS {
+		run_scenario(scenario, &initial_state, &yabai, &plugin);
+	}
+
+	fs::remove_dir_all(&plugin_dir).ok();
+}
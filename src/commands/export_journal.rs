@@ -0,0 +1,45 @@
+use serde_json::{json, Value};
+use std::fs;
+
+use crate::{
+	config::get_plugin_dir,
+	context::state::get_state_path,
+	journal::read_journal,
+	utils::args::{get_option_value, get_positional_args},
+};
+
+/// How many journal entries are exported unless `--count` says otherwise
+const DEFAULT_NUM_ENTRIES: usize = 50;
+
+/**
+ * Bundles the most recent journal entries with the plugin's version and state into one JSON file
+ * (or stdout if no file is given) that can be attached to an issue. The state is copied from
+ * state.json as is, since yabai may well be in a bad state when this is run.
+ */
+pub fn export_journal(args: &[String]) {
+	let num_entries = get_option_value(args, "--count")
+		.map(|count| count.parse().expect("Failed to parse --count"))
+		.unwrap_or(DEFAULT_NUM_ENTRIES);
+
+	let entries = read_journal(&get_plugin_dir());
+	let entries = &entries[entries.len().saturating_sub(num_entries)..];
+	if entries.is_empty() {
+		log::warn!(
+			"The journal is empty; set \"journal\": {{ \"enabled\": true }} in config.json to record it."
+		);
+	}
+
+	let bundle = json!({
+		"plugin_version": env!("CARGO_PKG_VERSION"),
+		"state": fs::read_to_string(get_state_path())
+			.ok()
+			.and_then(|state_str| serde_json::from_str::<Value>(&state_str).ok()),
+		"entries": entries,
+	});
+	let bundle_str = serde_json::to_string_pretty(&bundle).expect("Failed to stringify journal.");
+
+	match get_positional_args(args, &["--count"]).first() {
+		Some(path) => fs::write(path, bundle_str).expect("Failed to write exported journal"),
+		None => println!("{}", bundle_str),
+	}
+}
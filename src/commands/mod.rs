@@ -0,0 +1,5 @@
+mod export_journal;
+pub use export_journal::*;
+
+mod stats;
+pub use stats::*;
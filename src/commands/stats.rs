@@ -0,0 +1,109 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{metrics::read_metrics, utils::args::has_flag};
+
+#[derive(Serialize)]
+struct CommandStats {
+	command: String,
+	count: usize,
+	failures: usize,
+	p50_ms: f64,
+	p90_ms: f64,
+	p99_ms: f64,
+	max_ms: f64,
+	mean_yabai_commands: f64,
+}
+
+/**
+ * Returns the nearest-rank percentile of values that are sorted in ascending order
+ */
+fn get_percentile(sorted_values: &[f64], percentile: f64) -> f64 {
+	if sorted_values.is_empty() {
+		return 0.0;
+	}
+
+	let rank = (percentile / 100.0 * sorted_values.len() as f64).ceil() as usize;
+	sorted_values[rank.clamp(1, sorted_values.len()) - 1]
+}
+
+/**
+ * Summarizes how long each command took and how many yabai commands it issued, based on the
+ * recorded metrics. Invocations of `stats` itself aren't recorded.
+ */
+pub fn stats(args: &[String]) {
+	let mut metrics_by_command = BTreeMap::new();
+	for metrics in read_metrics() {
+		metrics_by_command
+			.entry(metrics.command.clone())
+			.or_insert_with(Vec::new)
+			.push(metrics);
+	}
+
+	let stats: Vec<CommandStats> = metrics_by_command
+		.into_iter()
+		.map(|(command, metrics)| {
+			let mut durations: Vec<f64> = metrics.iter().map(|m| m.duration_ms).collect();
+			durations.sort_by(|a, b| a.total_cmp(b));
+
+			CommandStats {
+				command,
+				count: metrics.len(),
+				failures: metrics.iter().filter(|m| !m.succeeded).count(),
+				p50_ms: get_percentile(&durations, 50.0),
+				p90_ms: get_percentile(&durations, 90.0),
+				p99_ms: get_percentile(&durations, 99.0),
+				max_ms: durations.last().copied().unwrap_or(0.0),
+				mean_yabai_commands: metrics.iter().map(|m| m.yabai_commands).sum::<usize>() as f64
+					/ metrics.len() as f64,
+			}
+		})
+		.collect();
+
+	if has_flag(args, "--json") {
+		println!(
+			"{}",
+			serde_json::to_string_pretty(&stats).expect("Failed to stringify stats.")
+		);
+		return;
+	}
+
+	if stats.is_empty() {
+		println!("No metrics have been recorded yet.");
+		return;
+	}
+
+	println!(
+		"{:<40} {:>6} {:>8} {:>9} {:>9} {:>9} {:>9} {:>7}",
+		"Command", "Count", "Failures", "p50 (ms)", "p90 (ms)", "p99 (ms)", "Max (ms)", "yabai"
+	);
+	for s in stats {
+		println!(
+			"{:<40} {:>6} {:>8} {:>9.1} {:>9.1} {:>9.1} {:>9.1} {:>7.1}",
+			s.command,
+			s.count,
+			s.failures,
+			s.p50_ms,
+			s.p90_ms,
+			s.p99_ms,
+			s.max_ms,
+			s.mean_yabai_commands
+		);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn computes_nearest_rank_percentiles() {
+		let values: Vec<f64> = (1..=10).map(|v| v as f64).collect();
+
+		assert_eq!(get_percentile(&values, 50.0), 5.0);
+		assert_eq!(get_percentile(&values, 90.0), 9.0);
+		assert_eq!(get_percentile(&values, 99.0), 10.0);
+		assert_eq!(get_percentile(&[3.0], 50.0), 3.0);
+		assert_eq!(get_percentile(&[], 50.0), 0.0);
+	}
+}
@@ -0,0 +1,359 @@
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use std::fs;
+
+use crate::types::Window;
+
+/// The directory that holds the plugin's config, state and lock files
+pub const PLUGIN_DIR: &str = "/Users/leonzalion/code/rusty-yabai-master-stack-plugin";
+
+/**
+ * Returns `PLUGIN_DIR`, unless the `MASTER_STACK_PLUGIN_DIR` environment variable points somewhere
+ * else (e.g. so that the benchmarks don't touch the real state)
+ */
+pub fn get_plugin_dir() -> String {
+	std::env::var("MASTER_STACK_PLUGIN_DIR").unwrap_or_else(|_| PLUGIN_DIR.to_string())
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+	/// When enabled, every trigger also updates the layout of the spaces that are visible on the
+	/// other displays instead of only the space the event happened on
+	pub manage_all_visible_spaces: bool,
+	/// Placement policies for specific windows; the first rule that matches a window applies
+	pub window_rules: Vec<WindowRule>,
+	/// Decides which kinds of windows are part of the layout (window rules take precedence)
+	pub window_filter: WindowFilter,
+	/// Where new windows are placed unless a window rule says otherwise
+	pub new_window_placement: WindowPlacement,
+	/// The empty space that yabai leaves around the windows; read from yabai's config when not set
+	pub padding: Option<Padding>,
+	/// How the plugin sends commands to yabai
+	pub yabai_backend: YabaiBackend,
+	/// The socket that the `socket` backend connects to (defaults to `/tmp/yabai_$USER.socket`)
+	pub yabai_socket_path: Option<String>,
+	pub logging: LoggingConfig,
+	pub journal: JournalConfig,
+	pub metrics: MetricsConfig,
+}
+
+/**
+ * The journal records every invocation (including window snapshots before and after it) to help
+ * reconstruct how a layout broke. Taking the snapshots costs two extra window queries per
+ * invocation, so it is off by default.
+ */
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct JournalConfig {
+	pub enabled: bool,
+	/// The size in kilobytes at which the journal is rotated
+	pub max_size_kb: u64,
+}
+
+impl Default for JournalConfig {
+	fn default() -> Self {
+		Self {
+			enabled: false,
+			max_size_kb: 4096,
+		}
+	}
+}
+
+/**
+ * The metrics record how long every invocation took and how many yabai commands it sent (see the
+ * `stats` command). Recording them only appends one line per invocation, so they are on by default.
+ */
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+	pub enabled: bool,
+}
+
+impl Default for MetricsConfig {
+	fn default() -> Self {
+		Self { enabled: true }
+	}
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+	/// A log filter in the format of `RUST_LOG` (e.g. `info`), which is overridden by `RUST_LOG` and
+	/// by the `-v` and `-q` flags
+	pub level: Option<String>,
+	/// Write the logs to `logs/plugin.log` in the plugin directory instead of stderr
+	pub file: bool,
+	pub format: LogFormat,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+	#[default]
+	Text,
+	/// One JSON object per line, including the ID of the invocation that logged it
+	Json,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum YabaiBackend {
+	/// Runs the yabai executable for every command
+	#[default]
+	Cli,
+	/// Sends the commands to yabai's Unix socket directly, which avoids spawning a process
+	Socket,
+}
+
+/**
+ * The space that yabai leaves between the left and right edges of a display and the windows on it.
+ * Windows are classified by their horizontal position, so the top and bottom padding (and the space
+ * reserved for an external bar) don't matter. The window gap only separates windows from each other,
+ * so it doesn't move the edges of the usable area either.
+ */
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct Padding {
+	pub left: f64,
+	pub right: f64,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowPlacement {
+	/// The window goes to the master if the master isn't full yet, and to the stack otherwise
+	#[default]
+	Auto,
+	/// The window becomes the top master window, pushing the bottom master window into the stack
+	AttachAsMaster,
+	/// The window goes to the top of the stack
+	AttachTop,
+	/// The window goes to the bottom of the stack
+	AttachBottom,
+	/// The window goes just below the window that was focused before it was created
+	AttachBelowFocused,
+}
+
+/**
+ * By default, only standard windows at the normal window level are managed, which leaves out
+ * dialogs, sheets, floating panels and windows that stay on top of or on every space
+ */
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct WindowFilter {
+	pub roles: Vec<String>,
+	pub subroles: Vec<String>,
+	pub levels: Vec<usize>,
+	pub manage_sticky_windows: bool,
+	pub manage_topmost_windows: bool,
+}
+
+impl Default for WindowFilter {
+	fn default() -> Self {
+		Self {
+			roles: vec!["AXWindow".to_string()],
+			subroles: vec!["AXStandardWindow".to_string()],
+			levels: vec![0],
+			manage_sticky_windows: false,
+			manage_topmost_windows: false,
+		}
+	}
+}
+
+impl WindowFilter {
+	pub fn matches(&self, window: &Window) -> bool {
+		self.roles.contains(&window.role)
+			&& self.subroles.contains(&window.subrole)
+			&& self.levels.contains(&window.level)
+			&& (self.manage_sticky_windows || window.sticky == 0)
+			&& (self.manage_topmost_windows || window.topmost == 0)
+	}
+}
+
+/**
+ * A rule matches a window if all of its conditions match; conditions that aren't set always match
+ */
+#[derive(Deserialize)]
+pub struct WindowRule {
+	pub app: Option<String>,
+	#[serde(default, deserialize_with = "deserialize_regex")]
+	pub title: Option<Regex>,
+	pub role: Option<String>,
+	pub subrole: Option<String>,
+	pub action: Option<WindowRuleAction>,
+	/// Overrides `new_window_placement` for the matching windows
+	pub placement: Option<WindowPlacement>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowRuleAction {
+	/// New windows are always placed in the master
+	AlwaysMaster,
+	/// New windows are always placed in the stack
+	AlwaysStack,
+	/// The window is left untouched and isn't part of the master/stack layout (combine it with a
+	/// yabai rule such as `manage=off` to keep yabai from tiling it in between the managed windows)
+	Ignore,
+	/// The window is made floating when it is created
+	Float,
+}
+
+fn deserialize_regex<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	Option::<String>::deserialize(deserializer)?
+		.map(|pattern| Regex::new(&pattern).map_err(serde::de::Error::custom))
+		.transpose()
+}
+
+impl WindowRule {
+	pub fn matches(&self, window: &Window) -> bool {
+		self.app.as_ref().is_none_or(|app| *app == window.app)
+			&& self
+				.title
+				.as_ref()
+				.is_none_or(|title| title.is_match(&window.title))
+			&& self.role.as_ref().is_none_or(|role| *role == window.role)
+			&& self
+				.subrole
+				.as_ref()
+				.is_none_or(|subrole| *subrole == window.subrole)
+	}
+}
+
+impl Config {
+	pub fn read() -> Self {
+		// If there is no config, use the defaults
+		match fs::read_to_string(format!("{}/config.json", get_plugin_dir())) {
+			Ok(config_str) => {
+				serde_json::from_str(&config_str).expect("Failed to parse config.json")
+			}
+			Err(_) => Config::default(),
+		}
+	}
+
+	/// Whether a window should be part of the layout according to the window rules and the window
+	/// filter (regardless of its space and whether it is floating or minimized)
+	pub fn should_manage_window(&self, window: &Window) -> bool {
+		match self.get_window_rule_action(window) {
+			Some(WindowRuleAction::Ignore | WindowRuleAction::Float) => false,
+			Some(WindowRuleAction::AlwaysMaster | WindowRuleAction::AlwaysStack) => true,
+			None => self.window_filter.matches(window),
+		}
+	}
+
+	pub fn get_window_rule(&self, window: &Window) -> Option<&WindowRule> {
+		self.window_rules.iter().find(|rule| rule.matches(window))
+	}
+
+	pub fn get_window_rule_action(&self, window: &Window) -> Option<WindowRuleAction> {
+		self.get_window_rule(window).and_then(|rule| rule.action)
+	}
+
+	pub fn get_new_window_placement(&self, window: &Window) -> WindowPlacement {
+		self.get_window_rule(window)
+			.and_then(|rule| rule.placement)
+			.unwrap_or(self.new_window_placement)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn get_window(fixture: &str) -> Window {
+		serde_json::from_str(fixture).expect("Failed to parse window fixture")
+	}
+
+	#[test]
+	fn default_filter_manages_standard_windows() {
+		let window = get_window(include_str!(
+			"../fixtures/yabai/windows/standard-window.json"
+		));
+
+		assert!(Config::default().should_manage_window(&window));
+	}
+
+	#[test]
+	fn default_filter_ignores_dialogs_and_panels() {
+		for fixture in [
+			include_str!("../fixtures/yabai/windows/dialog.json"),
+			include_str!("../fixtures/yabai/windows/preferences-pane.json"),
+			include_str!("../fixtures/yabai/windows/floating-panel.json"),
+			include_str!("../fixtures/yabai/windows/picture-in-picture.json"),
+		] {
+			let window = get_window(fixture);
+			assert!(
+				!Config::default().should_manage_window(&window),
+				"{} ({}) should not be managed",
+				window.title,
+				window.subrole
+			);
+		}
+	}
+
+	#[test]
+	fn filter_is_configurable() {
+		let config: Config = serde_json::from_str(
+			r#"{ "window_filter": { "subroles": ["AXStandardWindow", "AXDialog"] } }"#,
+		)
+		.unwrap();
+
+		let window = get_window(include_str!("../fixtures/yabai/windows/dialog.json"));
+		assert!(config.should_manage_window(&window));
+	}
+
+	#[test]
+	fn window_rules_take_precedence_over_filter() {
+		let config: Config = serde_json::from_str(
+			r#"{
+				"window_rules": [
+					{ "app": "Safari", "title": "^General$", "action": "always-stack" },
+					{ "app": "Terminal", "action": "ignore" }
+				]
+			}"#,
+		)
+		.unwrap();
+
+		let preferences_pane = get_window(include_str!(
+			"../fixtures/yabai/windows/preferences-pane.json"
+		));
+		assert!(config.should_manage_window(&preferences_pane));
+
+		let standard_window = get_window(include_str!(
+			"../fixtures/yabai/windows/standard-window.json"
+		));
+		assert!(!config.should_manage_window(&standard_window));
+	}
+
+	#[test]
+	fn window_rules_override_new_window_placement() {
+		let config: Config = serde_json::from_str(
+			r#"{
+				"new_window_placement": "attach-bottom",
+				"window_rules": [{ "app": "Terminal", "placement": "attach-as-master" }]
+			}"#,
+		)
+		.unwrap();
+
+		let terminal = get_window(include_str!(
+			"../fixtures/yabai/windows/standard-window.json"
+		));
+		assert_eq!(
+			config.get_new_window_placement(&terminal),
+			WindowPlacement::AttachAsMaster
+		);
+		assert_eq!(config.get_window_rule_action(&terminal), None);
+
+		let safari = get_window(include_str!(
+			"../fixtures/yabai/windows/preferences-pane.json"
+		));
+		assert_eq!(
+			config.get_new_window_placement(&safari),
+			WindowPlacement::AttachBottom
+		);
+	}
+}
@@ -1,12 +1,74 @@
-use crate::{context::YabaiPlugin, types::Display};
+use crate::{
+	config::Padding,
+	context::{
+		yabai::{Query, YabaiCommand},
+		YabaiPlugin,
+	},
+	types::{Display, Frame, Space},
+};
 
 impl YabaiPlugin {
 	pub fn get_displays(&self) -> Vec<Display> {
-		let displays = self.run_yabai_command("-m query --displays");
+		let displays = self
+			.run_yabai_command(&YabaiCommand::Query(Query::Displays))
+			.unwrap_or_else(|e| panic!("Failed to query displays: {:#}", e));
 		serde_json::from_str(&displays).expect("Failed to parse display ")
 	}
 	pub fn get_focused_display(&self) -> Display {
-		let display = self.run_yabai_command("-m query --displays --display");
+		let display = self
+			.run_yabai_command(&YabaiCommand::Query(Query::FocusedDisplay))
+			.unwrap_or_else(|e| panic!("Failed to query the focused display: {:#}", e));
 		serde_json::from_str(&display).expect("Failed to parse display")
 	}
+
+	/// Returns the padding of a space, either from the plugin's config or from yabai's config. Reading
+	/// it from yabai takes two commands, so it is only read once per space and invocation.
+	pub fn get_padding(&self, space: &Space) -> Padding {
+		if let Some(padding) = self.config.padding {
+			return padding;
+		}
+
+		if let Some(padding) = self.padding_cache.borrow().get(&space.index) {
+			return *padding;
+		}
+
+		let padding = self.read_padding_from_yabai(space);
+		self.padding_cache.borrow_mut().insert(space.index, padding);
+		padding
+	}
+
+	fn read_padding_from_yabai(&self, space: &Space) -> Padding {
+		let get_space_setting = |setting: &'static str| -> f64 {
+			self.run_yabai_command(&YabaiCommand::GetConfig {
+				space: Some(space.index),
+				setting,
+			})
+			.unwrap_or_else(|e| {
+				log::warn!("{:#}", e);
+				String::new()
+			})
+			.trim()
+			.parse()
+			.unwrap_or(0.0)
+		};
+
+		Padding {
+			left: get_space_setting("left_padding"),
+			right: get_space_setting("right_padding"),
+		}
+	}
+
+	/// Returns the part of the display that the windows of the space are laid out in. Only its left
+	/// and right edges are used to classify the windows, so the top and bottom padding (and the space
+	/// reserved for an external bar) are left out.
+	pub fn get_usable_frame(&self, display: &Display, space: &Space) -> Frame {
+		let padding = self.get_padding(space);
+
+		Frame::from_edges(
+			display.frame.left() + padding.left,
+			display.frame.top(),
+			display.frame.right() - padding.right,
+			display.frame.bottom(),
+		)
+	}
 }
@@ -2,12 +2,31 @@ pub mod display;
 pub mod space;
 pub mod state;
 pub mod window;
+pub mod yabai;
 
-use run_script::run_script;
+use std::{
+	cell::{Cell, RefCell},
+	collections::HashMap,
+	time::{Duration, Instant},
+};
+
+use crate::{
+	config::{Config, Padding, YabaiBackend},
+	journal, metrics,
+};
+use yabai::YabaiCommand;
+
+/// How long a yabai command may take before it is killed
+const YABAI_COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub struct YabaiPlugin {
 	pub yabai_path: &'static str,
-	pub debug: bool,
+	pub config: Config,
+	/// Whether the invocation has sent yabai a command that moves windows
+	pub moved_windows: Cell<bool>,
+	/// The padding of each space (by index) that has been read from yabai, since it doesn't change
+	/// while the plugin runs (see `get_padding`)
+	padding_cache: RefCell<HashMap<usize, Padding>>,
 }
 
 use load_dotenv::load_dotenv;
@@ -15,16 +34,48 @@ use load_dotenv::load_dotenv;
 load_dotenv!();
 
 impl YabaiPlugin {
-	pub fn new() -> Self {
+	pub fn new(config: Config) -> Self {
+		Self::with_yabai_path(env!("YABAI_PATH"), config)
+	}
+
+	pub fn with_yabai_path(yabai_path: &'static str, config: Config) -> Self {
 		YabaiPlugin {
-			yabai_path: env!("YABAI_PATH"),
-			debug: option_env!("DEBUG") == Some("1"),
+			yabai_path,
+			config,
+			moved_windows: Cell::new(false),
+			padding_cache: RefCell::new(HashMap::new()),
 		}
 	}
 
-	pub fn run_yabai_command(&self, command: &str) -> String {
-		let (_code, output, _error) =
-			run_script!(format!("{} {}", self.yabai_path, command)).expect("Failed to run script");
-		output
+	/// Runs a yabai command and returns its output. yabai rejects some commands in normal operation
+	/// (e.g. warping a window in a direction where there is no window), so it is up to the caller to
+	/// decide whether an error (which includes yabai's error message) matters.
+	pub fn run_yabai_command(&self, command: &YabaiCommand) -> anyhow::Result<String> {
+		journal::record_yabai_command(command);
+		if command.moves_windows() {
+			self.moved_windows.set(true);
+		}
+		let start_time = Instant::now();
+		let result = self.run_unrecorded_yabai_command(command);
+		metrics::record_yabai_command(start_time.elapsed());
+		result
+	}
+
+	/// Runs a yabai command without recording it in the journal or the metrics, for the plugin's own
+	/// bookkeeping (e.g. the journal's window snapshots)
+	pub fn run_unrecorded_yabai_command(&self, command: &YabaiCommand) -> anyhow::Result<String> {
+		match self.config.yabai_backend {
+			YabaiBackend::Cli => {
+				yabai::run_yabai_process(self.yabai_path, command, YABAI_COMMAND_TIMEOUT)
+			}
+			YabaiBackend::Socket => {
+				let socket_path = self
+					.config
+					.yabai_socket_path
+					.clone()
+					.unwrap_or_else(yabai::get_default_yabai_socket_path);
+				yabai::run_yabai_socket_command(&socket_path, command, YABAI_COMMAND_TIMEOUT)
+			}
+		}
 	}
 }
@@ -1,13 +1,32 @@
-use crate::{context::YabaiPlugin, types::Space};
+use crate::{
+	context::{
+		yabai::{Query, YabaiCommand},
+		YabaiPlugin,
+	},
+	types::Space,
+};
 
 impl YabaiPlugin {
 	pub fn get_spaces(&self) -> Vec<Space> {
-		let spaces = self.run_yabai_command("-m query --spaces");
+		let spaces = self
+			.run_yabai_command(&YabaiCommand::Query(Query::Spaces))
+			.unwrap_or_else(|e| panic!("Failed to query spaces: {:#}", e));
 		serde_json::from_str(&spaces).expect("Failed to parse spaces")
 	}
 
 	pub fn get_focused_space(&self) -> Space {
-		let space = self.run_yabai_command("-m query --spaces --space");
+		let space = self
+			.run_yabai_command(&YabaiCommand::Query(Query::FocusedSpace))
+			.unwrap_or_else(|e| panic!("Failed to query the focused space: {:#}", e));
 		serde_json::from_str(&space).expect("Failed to parse space")
 	}
-}
\ No newline at end of file
+
+	/// Finds a space by its label, or by its index if the selector is a number
+	pub fn get_space_by_selector(&self, selector: &str) -> Option<Space> {
+		let spaces = self.get_spaces();
+		match selector.parse::<usize>() {
+			Ok(index) => spaces.into_iter().find(|space| space.index == index),
+			Err(_) => spaces.into_iter().find(|space| space.label == selector),
+		}
+	}
+}
@@ -0,0 +1,235 @@
+use anyhow::bail;
+use serde_json::{Map, Value};
+use std::fmt;
+
+use super::get_space_key;
+use crate::types::{Display, Space};
+
+/**
+ * The version of the state file format that this version of the plugin writes. Whenever the format
+ * changes, this is bumped and a migration from the previous version is added to `migrate_state`.
+ *
+ * - 0: per-space state keyed by yabai's space IDs (state files from before versioning)
+ * - 1: per-space state keyed by space keys
+ * - 2: layouts store a single window order instead of separate master and stack windows
+ */
+pub const STATE_VERSION: u64 = 2;
+
+/**
+ * The state file was written by a newer version of the plugin. Unlike a corrupt state file, it is
+ * left in place, since rebuilding it would throw away the state of the newer version.
+ */
+#[derive(Debug)]
+pub struct UnsupportedStateVersion {
+	pub version: u64,
+}
+
+impl fmt::Display for UnsupportedStateVersion {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"state.json has version {}, which is newer than the supported version {}",
+			self.version, STATE_VERSION
+		)
+	}
+}
+
+impl std::error::Error for UnsupportedStateVersion {}
+
+pub fn get_state_version(state: &Value) -> u64 {
+	state.get("version").and_then(Value::as_u64).unwrap_or(0)
+}
+
+/**
+ * Migrates a state file written by an older version of the plugin to the current version, one
+ * version at a time
+ */
+pub fn migrate_state(
+	mut state: Value,
+	spaces: &[Space],
+	displays: &[Display],
+) -> anyhow::Result<Value> {
+	let mut version = get_state_version(&state);
+	if version > STATE_VERSION {
+		return Err(UnsupportedStateVersion { version }.into());
+	}
+
+	while version < STATE_VERSION {
+		state = match version {
+			0 => migrate_v0_to_v1(state, spaces, displays)?,
+			1 => migrate_v1_to_v2(state)?,
+			_ => unreachable!(),
+		};
+		version += 1;
+		state["version"] = version.into();
+	}
+
+	Ok(state)
+}
+
+/**
+ * Version 0 keys the per-space state by yabai's space IDs, which change whenever yabai restarts.
+ * Entries of spaces that no longer exist can't be mapped to a space key and are dropped.
+ */
+fn migrate_v0_to_v1(
+	mut state: Value,
+	spaces: &[Space],
+	displays: &[Display],
+) -> anyhow::Result<Value> {
+	for field in ["num_master_windows", "layouts"] {
+		let entries = match state.get_mut(field) {
+			Some(Value::Object(entries)) => std::mem::take(entries),
+			Some(Value::Null) | None => continue,
+			Some(_) => bail!("Expected {} to be an object", field),
+		};
+
+		let mut migrated_entries = Map::new();
+		for (key, value) in entries {
+			match key.parse::<usize>() {
+				Ok(space_id) => {
+					if let Some(space) = spaces.iter().find(|space| space.id == space_id) {
+						migrated_entries.insert(get_space_key(space, displays), value);
+					}
+				}
+				// Keys that aren't space IDs are already space keys
+				Err(_) => {
+					migrated_entries.insert(key, value);
+				}
+			}
+		}
+
+		state[field] = Value::Object(migrated_entries);
+	}
+
+	Ok(state)
+}
+
+/**
+ * Version 1 layouts are snapshots with separate lists of master and stack windows, which version 2
+ * replaces with one window order (master windows first)
+ */
+fn migrate_v1_to_v2(mut state: Value) -> anyhow::Result<Value> {
+	let layouts = match state.get_mut("layouts") {
+		Some(Value::Object(layouts)) => layouts,
+		Some(Value::Null) | None => return Ok(state),
+		Some(_) => bail!("Expected layouts to be an object"),
+	};
+
+	for layout in layouts.values_mut() {
+		let layout = match layout {
+			Value::Object(layout) => layout,
+			_ => bail!("Expected layout to be an object"),
+		};
+
+		let mut windows = vec![];
+		for field in ["master_windows", "stack_windows"] {
+			match layout.remove(field) {
+				Some(Value::Array(field_windows)) => windows.extend(field_windows),
+				Some(Value::Null) | None => {}
+				Some(_) => bail!("Expected {} to be an array", field),
+			}
+		}
+		layout.insert("windows".to_string(), Value::Array(windows));
+	}
+
+	Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json::json;
+
+	use super::*;
+	use crate::context::state::{
+		test_fixtures::{get_displays, get_spaces},
+		State,
+	};
+
+	#[test]
+	fn migrates_v0_space_ids_to_space_keys() {
+		let state = json!({
+			"num_master_windows": { "3": 2, "7": 1, "12": 3 },
+			"layouts": {
+				"7": { "master_windows": [10], "stack_windows": [11, 12], "focused_window": 11 }
+			}
+		});
+
+		let state = migrate_v0_to_v1(state, &get_spaces("code"), &get_displays()).unwrap();
+
+		assert_eq!(
+			state,
+			json!({
+				"num_master_windows": {
+					"37D8832A-2D66-02CA-B9F7-8F30A301B230:1": 2,
+					"label:code": 1
+				},
+				"layouts": {
+					"label:code": {
+						"master_windows": [10],
+						"stack_windows": [11, 12],
+						"focused_window": 11
+					}
+				}
+			})
+		);
+	}
+
+	#[test]
+	fn migrates_v0_without_layouts_to_current_version() {
+		let state = json!({ "num_master_windows": { "3": 1 } });
+
+		let state = migrate_state(state, &get_spaces("code"), &get_displays()).unwrap();
+
+		assert_eq!(get_state_version(&state), STATE_VERSION);
+		serde_json::from_value::<State>(state).unwrap();
+	}
+
+	#[test]
+	fn keeps_v0_entries_that_are_already_space_keys() {
+		let state = json!({ "num_master_windows": { "label:code": 2, "3": 1 } });
+
+		let state = migrate_v0_to_v1(state, &get_spaces("code"), &get_displays()).unwrap();
+
+		assert_eq!(state["num_master_windows"]["label:code"], json!(2));
+	}
+
+	#[test]
+	fn migrates_v1_layouts_to_window_order() {
+		let state = json!({
+			"version": 1,
+			"num_master_windows": { "label:code": 1 },
+			"layouts": {
+				"label:code": { "master_windows": [10], "stack_windows": [11, 12], "focused_window": 11 },
+				"label:web": { "master_windows": [], "stack_windows": [], "focused_window": null }
+			}
+		});
+
+		let state = migrate_v1_to_v2(state).unwrap();
+
+		assert_eq!(
+			state["layouts"],
+			json!({
+				"label:code": { "windows": [10, 11, 12], "focused_window": 11 },
+				"label:web": { "windows": [], "focused_window": null }
+			})
+		);
+	}
+
+	#[test]
+	fn migrates_v1_without_layouts() {
+		let state = json!({ "version": 1, "num_master_windows": { "label:code": 1 } });
+
+		let state = migrate_state(state, &[], &[]).unwrap();
+
+		assert_eq!(get_state_version(&state), STATE_VERSION);
+		serde_json::from_value::<State>(state).unwrap();
+	}
+
+	#[test]
+	fn rejects_newer_versions() {
+		let state = json!({ "version": STATE_VERSION + 1, "num_master_windows": {} });
+
+		let error = migrate_state(state, &get_spaces("code"), &get_displays()).unwrap_err();
+		assert!(error.downcast_ref::<UnsupportedStateVersion>().is_some());
+	}
+}
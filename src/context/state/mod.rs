@@ -0,0 +1,676 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{hash_map::Entry, HashMap};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{
+	yabai::{Query, YabaiCommand},
+	YabaiPlugin,
+};
+use crate::{
+	config::{get_plugin_dir, WindowPlacement},
+	types::{Display, Frame, Space, Window},
+};
+
+mod migrations;
+#[cfg(test)]
+mod test_fixtures;
+pub use migrations::*;
+
+/// Per-space state is keyed by the space keys returned by `get_space_key`
+#[derive(Deserialize, Serialize)]
+pub struct State {
+	/// The format version of the state file (see `migrations.rs`)
+	pub version: u64,
+	pub num_master_windows: HashMap<String, usize>,
+	/// The window order of each space, which is also used to repair the layout after a window
+	/// disappears (at which point it can no longer be queried)
+	#[serde(default)]
+	pub layouts: HashMap<String, Layout>,
+	/// Where the last invocation that moved windows left each window (by window ID). yabai reports
+	/// the plugin's own moves as window_moved events, which are matched against these frames (see
+	/// `is_own_window_move`).
+	#[serde(default)]
+	pub own_window_moves: HashMap<usize, Frame>,
+}
+
+pub fn get_state_path() -> String {
+	format!("{}/state.json", get_plugin_dir())
+}
+
+/**
+ * Returns a key for a space that stays the same when yabai restarts or when spaces on other displays
+ * are created, destroyed or reordered (unlike the space's ID and index). Labelled spaces are keyed by
+ * their label, and other spaces by their display's UUID and their position on that display.
+ */
+pub fn get_space_key(space: &Space, displays: &[Display]) -> String {
+	if !space.label.is_empty() {
+		return format!("label:{}", space.label);
+	}
+
+	get_positional_space_key(space, displays)
+}
+
+fn get_positional_space_key(space: &Space, displays: &[Display]) -> String {
+	match displays.iter().find(|d| d.index == space.display) {
+		Some(display) => {
+			let position = display
+				.spaces
+				.iter()
+				.position(|index| *index == space.index)
+				.map(|position| position + 1)
+				.unwrap_or(space.index);
+			format!("{}:{}", display.uuid, position)
+		}
+		None => format!("space:{}", space.index),
+	}
+}
+
+/**
+ * The authoritative order of the managed windows on a space. The first `num_master_windows`
+ * windows are the master windows and the rest are the stack windows, each from top to bottom.
+ * Commands update the order, and the windows are then rearranged to match it (see
+ * `WindowsManager::apply_window_order`).
+ */
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Layout {
+	pub windows: Vec<usize>,
+	pub focused_window: Option<usize>,
+}
+
+impl Layout {
+	pub fn contains_window(&self, window_id: usize) -> bool {
+		self.windows.contains(&window_id)
+	}
+
+	pub fn get_master_windows(&self, num_master_windows: usize) -> &[usize] {
+		&self.windows[..num_master_windows.min(self.windows.len())]
+	}
+
+	pub fn get_stack_windows(&self, num_master_windows: usize) -> &[usize] {
+		&self.windows[num_master_windows.min(self.windows.len())..]
+	}
+
+	/// Inserts a window at the given position (or at the end if the position is past the end), moving
+	/// it there if it is already part of the layout
+	pub fn insert_window(&mut self, window_id: usize, position: usize) {
+		self.windows.retain(|id| *id != window_id);
+		self.windows
+			.insert(position.min(self.windows.len()), window_id);
+	}
+
+	/// Swaps a window with the window before it in the order (i.e. above it, or at the bottom of the
+	/// master column for the top stack window), wrapping around to the last window
+	pub fn swap_window_up(&mut self, window_id: usize) {
+		if let Some(position) = self.windows.iter().position(|id| *id == window_id) {
+			let num_windows = self.windows.len();
+			self.windows
+				.swap(position, (position + num_windows - 1) % num_windows);
+		}
+	}
+
+	/// Swaps a window with the window after it in the order, wrapping around to the first window
+	pub fn swap_window_down(&mut self, window_id: usize) {
+		if let Some(position) = self.windows.iter().position(|id| *id == window_id) {
+			let num_windows = self.windows.len();
+			self.windows.swap(position, (position + 1) % num_windows);
+		}
+	}
+
+	/// Inserts a new window into the window order according to its placement, and returns whether
+	/// it was inserted. `Auto` depends on where yabai tiled the window, and `AttachBelowFocused` falls
+	/// back to it when the previously focused window isn't part of the layout, so those are left to
+	/// the caller.
+	pub fn attach_window(
+		&mut self,
+		window_id: usize,
+		placement: WindowPlacement,
+		num_master_windows: usize,
+		focused_window: Option<usize>,
+	) -> bool {
+		let other_windows = || self.windows.iter().filter(|id| **id != window_id);
+		let position = match placement {
+			WindowPlacement::Auto => None,
+			WindowPlacement::AttachAsMaster => Some(0),
+			WindowPlacement::AttachTop => Some(num_master_windows),
+			WindowPlacement::AttachBottom => Some(usize::MAX),
+			WindowPlacement::AttachBelowFocused => focused_window
+				.and_then(|focused_window| other_windows().position(|id| *id == focused_window))
+				.map(|position| position + 1),
+		};
+
+		match position {
+			Some(position) => {
+				self.insert_window(window_id, position);
+				true
+			}
+			None => false,
+		}
+	}
+
+	pub fn remove_window(&mut self, window_id: usize) {
+		self.windows.retain(|id| *id != window_id);
+		if self.focused_window == Some(window_id) {
+			self.focused_window = None;
+		}
+	}
+
+	/// Returns the window that should receive focus once the window with the given ID is closed:
+	/// the window above it in the same column, or the window below it if it is the top window. If the
+	/// window is the only one in its column, focus moves over to the other column.
+	pub fn get_window_to_focus_after_close(
+		&self,
+		window_id: usize,
+		num_master_windows: usize,
+	) -> Option<usize> {
+		let master_windows = self.get_master_windows(num_master_windows);
+		let stack_windows = self.get_stack_windows(num_master_windows);
+
+		if let Some(position) = stack_windows.iter().position(|id| *id == window_id) {
+			// If the window is the only stack window, then focus on the master window
+			if stack_windows.len() == 1 {
+				return master_windows.first().copied();
+			}
+
+			// Focus on the window above it, or if there is no window above it, then the window below it
+			if position == 0 {
+				stack_windows.get(1).copied()
+			} else {
+				stack_windows.get(position - 1).copied()
+			}
+		} else if let Some(position) = master_windows.iter().position(|id| *id == window_id) {
+			// If the window is the only master window and there is at least one stack window,
+			// focus on the bottom stack window
+			if master_windows.len() == 1 && !stack_windows.is_empty() {
+				return stack_windows.last().copied();
+			}
+
+			// Focus on the window above it, or if there is no window above it, then the window below it
+			if position == 0 {
+				master_windows.get(1).copied()
+			} else {
+				master_windows.get(position - 1).copied()
+			}
+		} else {
+			None
+		}
+	}
+}
+
+impl State {
+	pub fn default(context: &YabaiPlugin) -> Self {
+		let spaces = context.get_spaces();
+		let displays = context.get_displays();
+		let mut num_master_windows = HashMap::new();
+		for space in spaces {
+			num_master_windows.insert(get_space_key(&space, &displays), 1);
+		}
+
+		Self {
+			version: STATE_VERSION,
+			num_master_windows,
+			layouts: HashMap::new(),
+			own_window_moves: HashMap::new(),
+		}
+	}
+
+	/// Whether the window is still where the plugin's last invocation that moved windows left it, in
+	/// which case a window_moved event for it was caused by the plugin. Once the window has been moved
+	/// somewhere else, its recorded move is dropped, so that moving it back is noticed as well.
+	pub fn match_own_window_move(&mut self, window: &Window) -> bool {
+		match self.own_window_moves.get(&window.id) {
+			Some(frame) if frame.approx_eq(&window.frame) => true,
+			Some(_) => {
+				self.own_window_moves.remove(&window.id);
+				false
+			}
+			None => false,
+		}
+	}
+
+	/// Reconciles the per-space state with the spaces that currently exist:
+	/// - state stored under a space's positional key is moved to its label key once the space has been
+	///   labelled, so that the state follows the space from then on
+	/// - entries of spaces that no longer exist on a connected display are removed, since their
+	///   positional key would otherwise be inherited by the next space created at that position
+	/// - spaces without an entry get the default master window count
+	///
+	/// Entries of labelled spaces and of displays that aren't connected are kept, since the display
+	/// might only be unplugged (see `remove_missing_spaces`). Returns whether the state changed.
+	pub fn reconcile(&mut self, spaces: &[Space], displays: &[Display]) -> bool {
+		let mut changed = false;
+
+		for space in spaces {
+			if space.label.is_empty() {
+				continue;
+			}
+
+			let label_key = get_space_key(space, displays);
+			let positional_key = get_positional_space_key(space, displays);
+			if let Some(num_master_windows) = self.num_master_windows.remove(&positional_key) {
+				self.num_master_windows
+					.entry(label_key.clone())
+					.or_insert(num_master_windows);
+				changed = true;
+			}
+			if let Some(layout) = self.layouts.remove(&positional_key) {
+				self.layouts.entry(label_key).or_insert(layout);
+				changed = true;
+			}
+		}
+
+		let space_keys: Vec<String> = spaces
+			.iter()
+			.map(|space| get_space_key(space, displays))
+			.collect();
+		let is_dead_space_key = |key: &String| {
+			!space_keys.contains(key)
+				&& (key.starts_with("space:")
+					|| displays
+						.iter()
+						.any(|display| key.starts_with(&format!("{}:", display.uuid))))
+		};
+		let num_entries = self.num_master_windows.len() + self.layouts.len();
+		self.num_master_windows
+			.retain(|key, _| !is_dead_space_key(key));
+		self.layouts.retain(|key, _| !is_dead_space_key(key));
+		let num_removed_entries = num_entries - self.num_master_windows.len() - self.layouts.len();
+		if num_removed_entries > 0 {
+			log::debug!(
+				"Removed {} state entries of spaces that were destroyed.",
+				num_removed_entries
+			);
+			changed = true;
+		}
+
+		for space_key in space_keys {
+			if let Entry::Vacant(entry) = self.num_master_windows.entry(space_key) {
+				log::debug!("Adding state for new space {}.", entry.key());
+				entry.insert(1);
+				changed = true;
+			}
+		}
+
+		changed
+	}
+
+	/// Removes the entries of spaces that don't currently exist. Returns whether any were removed.
+	pub fn remove_missing_spaces(&mut self, spaces: &[Space], displays: &[Display]) -> bool {
+		let space_keys: Vec<String> = spaces
+			.iter()
+			.map(|space| get_space_key(space, displays))
+			.collect();
+
+		let num_entries = self.num_master_windows.len() + self.layouts.len();
+		self.num_master_windows
+			.retain(|key, _| space_keys.contains(key));
+		self.layouts.retain(|key, _| space_keys.contains(key));
+		let num_removed_entries = num_entries - self.num_master_windows.len() - self.layouts.len();
+		if num_removed_entries > 0 {
+			log::debug!(
+				"Removed {} state entries of spaces that no longer exist.",
+				num_removed_entries
+			);
+		}
+
+		num_removed_entries > 0
+	}
+}
+
+impl YabaiPlugin {
+	pub fn read_state(&self) -> State {
+		// If the state doesn't exist, create it
+		let state_str = match fs::read_to_string(get_state_path()) {
+			Ok(state_str) => state_str,
+			Err(_) => return State::default(self),
+		};
+
+		match self.parse_state(&state_str) {
+			Ok((state, migrated)) => {
+				if migrated {
+					self.write_state(&state);
+				}
+				state
+			}
+			Err(e) if e.is::<UnsupportedStateVersion>() => {
+				panic!("{}; upgrade the plugin to use this state file.", e)
+			}
+			// A corrupt state file would otherwise make every command fail, so it is moved out of
+			// the way (to keep it around for debugging) and the state is rebuilt from scratch
+			Err(e) => {
+				let backup_path = format!(
+					"{}.corrupt-{}",
+					get_state_path(),
+					SystemTime::now()
+						.duration_since(UNIX_EPOCH)
+						.map(|d| d.as_secs())
+						.unwrap_or(0)
+				);
+				log::error!(
+					"Failed to parse state.json ({}); moving it to {} and rebuilding the state.",
+					e,
+					backup_path
+				);
+				fs::rename(get_state_path(), &backup_path).expect("Failed to back up state.json");
+
+				let state = State::default(self);
+				self.write_state(&state);
+				state
+			}
+		}
+	}
+
+	/// Parses the state file, migrating it if it was written by an older version of the plugin. Also
+	/// returns whether a migration took place.
+	pub fn parse_state(&self, state_str: &str) -> anyhow::Result<(State, bool)> {
+		let state: serde_json::Value = serde_json::from_str(state_str)?;
+		let version = get_state_version(&state);
+		if version == STATE_VERSION {
+			return Ok((serde_json::from_value(state)?, false));
+		}
+		if version > STATE_VERSION {
+			return Err(UnsupportedStateVersion { version }.into());
+		}
+
+		log::debug!(
+			"Migrating state from version {} to version {}.",
+			version,
+			STATE_VERSION
+		);
+		let state = migrate_state(state, &self.get_spaces(), &self.get_displays())?;
+		Ok((serde_json::from_value(state)?, true))
+	}
+
+	/// The state is written to a temporary file that then replaces the state file, so that a crash or
+	/// a concurrent invocation never leaves a partially written state file behind
+	pub fn write_state(&self, state: &State) {
+		let state_path = get_state_path();
+		let temp_path = format!("{}.{}.tmp", state_path, std::process::id());
+		fs::write(
+			&temp_path,
+			serde_json::to_string(state).expect("Failed to stringify state."),
+		)
+		.expect("Failed to write state");
+		fs::rename(&temp_path, &state_path).expect("Failed to replace state.json");
+	}
+
+	pub fn read_layout(&self, space_key: &str) -> Option<Layout> {
+		self.read_state().layouts.get(space_key).cloned()
+	}
+
+	pub fn write_layout(&self, space_key: &str, layout: Layout) {
+		let mut state = self.read_state();
+		state.layouts.insert(space_key.to_string(), layout);
+		self.write_state(&state);
+	}
+
+	/// Records where the current invocation has left the windows after moving them. yabai reports the
+	/// moves as window_moved events, which only get to run once this invocation has released the
+	/// lock.
+	pub fn record_own_window_moves(&self) {
+		let windows = self
+			.run_yabai_command(&YabaiCommand::Query(Query::Windows))
+			.and_then(|output| Ok(serde_json::from_str::<Vec<Window>>(&output)?));
+		let windows = match windows {
+			Ok(windows) => windows,
+			Err(e) => {
+				log::warn!("Failed to record the windows that were moved: {:#}", e);
+				return;
+			}
+		};
+
+		let mut state = self.read_state();
+		state.own_window_moves = windows
+			.into_iter()
+			.map(|window| (window.id, window.frame))
+			.collect();
+		self.write_state(&state);
+	}
+
+	/// Whether a window_moved event for the window was caused by the plugin's own moves (see
+	/// `State::match_own_window_move`)
+	pub fn is_own_window_move(&self, window: &Window) -> bool {
+		let mut state = self.read_state();
+		let num_own_window_moves = state.own_window_moves.len();
+		let is_own_window_move = state.match_own_window_move(window);
+		if state.own_window_moves.len() != num_own_window_moves {
+			self.write_state(&state);
+		}
+		is_own_window_move
+	}
+
+	/// Brings the state in line with the spaces that currently exist (see `State::reconcile`)
+	pub fn reconcile_state(&self) {
+		let mut state = self.read_state();
+		if state.reconcile(&self.get_spaces(), &self.get_displays()) {
+			self.write_state(&state);
+		}
+	}
+
+	/// Reconciles the state and also removes the entries of spaces that don't currently exist
+	pub fn gc_state(&self) {
+		let mut state = self.read_state();
+		let spaces = self.get_spaces();
+		let displays = self.get_displays();
+		let reconciled = state.reconcile(&spaces, &displays);
+		if state.remove_missing_spaces(&spaces, &displays) || reconciled {
+			self.write_state(&state);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{
+		test_fixtures::{get_displays, get_spaces, UUID},
+		*,
+	};
+
+	const UNPLUGGED_UUID: &str = "9A1B2C3D-0000-1111-2222-333344445555";
+
+	fn get_state(num_master_windows: &[(&str, usize)]) -> State {
+		State {
+			version: STATE_VERSION,
+			num_master_windows: num_master_windows
+				.iter()
+				.map(|(key, n)| (key.to_string(), *n))
+				.collect(),
+			layouts: HashMap::new(),
+			own_window_moves: HashMap::new(),
+		}
+	}
+
+	#[test]
+	fn reconcile_keeps_spaces_of_unplugged_displays() {
+		let unplugged_key = format!("{}:1", UNPLUGGED_UUID);
+		let mut state = get_state(&[(&unplugged_key, 3)]);
+
+		assert!(state.reconcile(&get_spaces(""), &get_displays()));
+
+		assert_eq!(state.num_master_windows[&unplugged_key], 3);
+		assert_eq!(state.num_master_windows[&format!("{}:1", UUID)], 1);
+		assert_eq!(state.num_master_windows[&format!("{}:2", UUID)], 1);
+		assert!(!state.reconcile(&get_spaces(""), &get_displays()));
+	}
+
+	#[test]
+	fn reconcile_moves_state_to_the_label_key() {
+		let positional_key = format!("{}:2", UUID);
+		let mut state = get_state(&[(&positional_key, 2)]);
+		state.layouts.insert(
+			positional_key.clone(),
+			Layout {
+				windows: vec![10, 11],
+				focused_window: None,
+			},
+		);
+
+		assert!(state.reconcile(&get_spaces("code"), &get_displays()));
+
+		assert_eq!(state.num_master_windows["label:code"], 2);
+		assert_eq!(state.layouts["label:code"].windows, vec![10, 11]);
+		assert!(!state.num_master_windows.contains_key(&positional_key));
+		assert!(!state.layouts.contains_key(&positional_key));
+	}
+
+	#[test]
+	fn reconcile_removes_destroyed_spaces_of_connected_displays() {
+		let destroyed_key = format!("{}:3", UUID);
+		let unplugged_key = format!("{}:1", UNPLUGGED_UUID);
+		let mut state = get_state(&[(&destroyed_key, 2), (&unplugged_key, 3), ("label:old", 2)]);
+		state
+			.layouts
+			.insert(destroyed_key.clone(), Layout::default());
+
+		assert!(state.reconcile(&get_spaces(""), &get_displays()));
+
+		assert!(!state.num_master_windows.contains_key(&destroyed_key));
+		assert!(!state.layouts.contains_key(&destroyed_key));
+		assert_eq!(state.num_master_windows[&unplugged_key], 3);
+		assert_eq!(state.num_master_windows["label:old"], 2);
+	}
+
+	#[test]
+	fn removes_missing_spaces_only_when_asked() {
+		let key = format!("{}:1", UUID);
+		let unplugged_key = format!("{}:1", UNPLUGGED_UUID);
+		let mut state = get_state(&[(&key, 2), (&unplugged_key, 3)]);
+
+		assert!(state.remove_missing_spaces(&get_spaces(""), &get_displays()));
+
+		assert_eq!(state.num_master_windows[&key], 2);
+		assert!(!state.num_master_windows.contains_key(&unplugged_key));
+		assert!(!state.remove_missing_spaces(&get_spaces(""), &get_displays()));
+	}
+
+	#[test]
+	fn swaps_windows_with_their_neighbors() {
+		let mut layout = Layout {
+			windows: vec![1, 2, 3],
+			focused_window: None,
+		};
+
+		layout.swap_window_up(2);
+		assert_eq!(layout.windows, vec![2, 1, 3]);
+		layout.swap_window_up(2);
+		assert_eq!(layout.windows, vec![3, 1, 2]);
+		layout.swap_window_down(2);
+		assert_eq!(layout.windows, vec![2, 1, 3]);
+		layout.swap_window_down(4);
+		assert_eq!(layout.windows, vec![2, 1, 3]);
+	}
+
+	#[test]
+	fn focuses_the_window_above_after_close_or_the_window_below_at_the_top() {
+		// Windows 1 and 2 are the master windows, and windows 3 to 5 are the stack windows
+		let layout = Layout {
+			windows: vec![1, 2, 3, 4, 5],
+			focused_window: None,
+		};
+
+		assert_eq!(layout.get_window_to_focus_after_close(4, 2), Some(3));
+		assert_eq!(layout.get_window_to_focus_after_close(3, 2), Some(4));
+		assert_eq!(layout.get_window_to_focus_after_close(2, 2), Some(1));
+		assert_eq!(layout.get_window_to_focus_after_close(1, 2), Some(2));
+		assert_eq!(layout.get_window_to_focus_after_close(6, 2), None);
+	}
+
+	#[test]
+	fn focuses_the_other_column_after_closing_the_only_window_in_a_column() {
+		let layout = Layout {
+			windows: vec![1, 2, 3],
+			focused_window: None,
+		};
+
+		// The bottom stack window for the only master window, and the top master window for the only
+		// stack window
+		assert_eq!(layout.get_window_to_focus_after_close(1, 1), Some(3));
+		assert_eq!(layout.get_window_to_focus_after_close(3, 2), Some(1));
+
+		let single_window_layout = Layout {
+			windows: vec![1],
+			focused_window: None,
+		};
+		assert_eq!(
+			single_window_layout.get_window_to_focus_after_close(1, 1),
+			None
+		);
+	}
+
+	#[test]
+	fn attaches_new_windows_to_the_master_or_the_top_or_bottom_of_the_stack() {
+		// Windows 1 and 2 are the master windows, and windows 3 and 4 are the stack windows
+		let attach = |placement| {
+			let mut layout = Layout {
+				windows: vec![1, 2, 3, 4],
+				focused_window: None,
+			};
+			assert!(layout.attach_window(5, placement, 2, None));
+			layout.windows
+		};
+
+		// Window 2 is pushed into the stack
+		assert_eq!(attach(WindowPlacement::AttachAsMaster), vec![5, 1, 2, 3, 4]);
+		assert_eq!(attach(WindowPlacement::AttachTop), vec![1, 2, 5, 3, 4]);
+		assert_eq!(attach(WindowPlacement::AttachBottom), vec![1, 2, 3, 4, 5]);
+	}
+
+	#[test]
+	fn attaches_new_windows_below_the_focused_window() {
+		let attach = |windows: &[usize], focused_window| {
+			let mut layout = Layout {
+				windows: windows.to_vec(),
+				focused_window: None,
+			};
+			let attached =
+				layout.attach_window(5, WindowPlacement::AttachBelowFocused, 2, focused_window);
+			(attached, layout.windows)
+		};
+
+		// Below a master window (pushing the bottom master window into the stack) and below the top
+		// and the bottom stack window
+		assert_eq!(attach(&[1, 2, 3, 4], Some(1)), (true, vec![1, 5, 2, 3, 4]));
+		assert_eq!(attach(&[1, 2, 3, 4], Some(3)), (true, vec![1, 2, 3, 5, 4]));
+		assert_eq!(attach(&[1, 2, 3, 4], Some(4)), (true, vec![1, 2, 3, 4, 5]));
+		// yabai may have already added the new window to the layout, above the focused window
+		assert_eq!(
+			attach(&[1, 5, 2, 3, 4], Some(3)),
+			(true, vec![1, 2, 3, 5, 4])
+		);
+
+		// Without a focused window in the layout, the window is left to the automatic placement
+		assert_eq!(attach(&[1, 2, 3, 4], None), (false, vec![1, 2, 3, 4]));
+		assert_eq!(attach(&[1, 2, 3, 4], Some(6)), (false, vec![1, 2, 3, 4]));
+		assert_eq!(
+			attach(&[1, 2, 3, 4, 5], Some(5)),
+			(false, vec![1, 2, 3, 4, 5])
+		);
+	}
+
+	#[test]
+	fn matches_window_moves_against_where_the_plugin_left_the_windows() {
+		let mut window: Window = serde_json::from_str(include_str!(
+			"../../../fixtures/yabai/windows/standard-window.json"
+		))
+		.unwrap();
+		let mut state = get_state(&[]);
+		state
+			.own_window_moves
+			.insert(window.id, window.frame.clone());
+
+		// Every event for the window is the plugin's own for as long as the window stays in place
+		assert!(state.match_own_window_move(&window));
+		assert!(state.match_own_window_move(&window));
+
+		// The user moved the window, and moving it back isn't the plugin's doing either
+		let recorded_frame = window.frame.clone();
+		window.frame.x += 100.0;
+		assert!(!state.match_own_window_move(&window));
+		window.frame = recorded_frame;
+		assert!(!state.match_own_window_move(&window));
+
+		// Windows that the plugin didn't move
+		window.id += 1;
+		assert!(!state.match_own_window_move(&window));
+	}
+}
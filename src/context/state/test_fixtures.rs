@@ -0,0 +1,39 @@
+/*!
+ * The displays and spaces that the state tests reconcile and migrate the state against
+ */
+
+use serde_json::json;
+
+use crate::types::{Display, Space};
+
+/// The UUID of the only display
+pub const UUID: &str = "37D8832A-2D66-02CA-B9F7-8F30A301B230";
+
+/**
+ * Returns one display with spaces 1 and 2
+ */
+pub fn get_displays() -> Vec<Display> {
+	serde_json::from_value(json!([{
+		"id": 1,
+		"uuid": UUID,
+		"index": 1,
+		"spaces": [1, 2],
+		"frame": { "x": 0.0, "y": 0.0, "w": 1440.0, "h": 900.0 }
+	}]))
+	.unwrap()
+}
+
+/**
+ * Returns the spaces of the display: space 1 (ID 3) without a label, and space 2 (ID 7) with the
+ * given label
+ */
+pub fn get_spaces(label: &str) -> Vec<Space> {
+	let space = |id: usize, index: usize, label: &str| {
+		json!({
+			"id": id, "label": label, "index": index, "display": 1, "windows": [],
+			"type": "bsp", "visible": 0, "focused": 0, "native-fullscreen": 0,
+			"first-window": 0, "last-window": 0
+		})
+	};
+	serde_json::from_value(json!([space(3, 1, ""), space(7, 2, label)])).unwrap()
+}
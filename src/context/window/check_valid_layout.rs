@@ -14,7 +14,7 @@ impl WindowsManager<'_> {
 		log::debug!("Starting valid layout check...");
 
 		// If there are no windows, it is a valid layout
-		if self.windows_data.len() == 0 {
+		if self.windows_data.is_empty() {
 			return CheckValidLayoutPayload::Success;
 		}
 
@@ -27,9 +27,9 @@ impl WindowsManager<'_> {
 			&& !self
 				.windows_data
 				.iter()
-				.all(|w| self.is_window_touching_left_edge(&w))
+				.all(|w| self.is_window_touching_left_edge(w))
 		{
-			return CheckValidLayoutPayload::Failure("The number of master windows is greater or equal to the number of windows and not all windows are touching the left edge.".to_string());
+			CheckValidLayoutPayload::Failure("The number of master windows is greater or equal to the number of windows and not all windows are touching the left edge.".to_string())
 		} else {
 			// Verify that the number of master windows equals the target number of master windows
 			let cur_num_master_windows = self.get_master_windows().len();
@@ -40,7 +40,7 @@ impl WindowsManager<'_> {
 
 			// Verify that there is no middle window
 			for window in &self.windows_data {
-				if self.is_middle_window(&window) {
+				if self.is_middle_window(window) {
 					return CheckValidLayoutPayload::Failure(format!(
 						"A middle window ({}) was detected.",
 						window.app
@@ -48,7 +48,7 @@ impl WindowsManager<'_> {
 				}
 			}
 
-			return CheckValidLayoutPayload::Success;
+			CheckValidLayoutPayload::Success
 		}
 	}
 }
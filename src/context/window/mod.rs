@@ -1,122 +1,250 @@
+use std::cell::{Ref, RefCell};
+
 use crate::{
-	context::YabaiPlugin,
+	context::{
+		yabai::{Direction, Query, WindowSelector, WindowToggle, YabaiCommand},
+		YabaiPlugin,
+	},
 	some_or_return,
-	types::{Display, Space, Window},
+	types::{Display, Frame, Space, Window},
+	utils::geometry::{approx_eq, approx_ge, approx_gt, approx_le, approx_lt},
 };
 
-use super::state::State;
+use super::state::{get_space_key, Layout, State};
 
 mod check_valid_layout;
 pub use check_valid_layout::*;
 
+mod place_new_window;
+
+mod rebuild_layout;
+
+mod relayout;
+pub use relayout::*;
+
+#[cfg(test)]
+mod test_fixtures;
+
 mod update_windows;
 pub use update_windows::*;
 
+mod window_order;
+
 pub struct WindowsManager<'p> {
 	pub display: Display,
 	pub space: Space,
+	/// The key of the space's entries in the state
+	pub space_key: String,
 	pub expected_current_num_master_windows: usize,
 	pub plugin: &'p YabaiPlugin,
 	pub windows_data: Vec<Window>,
+	/// The window order that the windows are arranged in (see `Layout`)
+	pub layout: Layout,
+	/// The part of the display that the windows are laid out in (excluding the left and right padding)
+	pub usable_frame: Frame,
+	/// The classification of the current windows data, which most helpers need. Cleared whenever the
+	/// windows data is refreshed.
+	classification: RefCell<Option<WindowClassification>>,
 }
 
-pub fn create_windows_manager(plugin: &YabaiPlugin) -> WindowsManager {
-	let mut state = plugin.read_state();
-	let display = plugin.get_focused_display();
+/**
+ * The managed windows split into master, stack and middle windows, for a master window count
+ */
+struct WindowClassification {
+	num_master_windows: usize,
+	dividing_line_x_coordinate: f64,
+	master_windows: Vec<Window>,
+	stack_windows: Vec<Window>,
+	middle_windows: Vec<Window>,
+}
 
+pub fn create_windows_manager(plugin: &YabaiPlugin) -> WindowsManager<'_> {
+	let display = plugin.get_focused_display();
 	let space = plugin.get_focused_space();
 
-	let expected_current_num_master_windows = state.num_master_windows[&space.id];
+	create_windows_manager_for_space(plugin, display, space)
+}
+
+/**
+ * Creates a windows manager for the space that the window is on, which might not be the focused
+ * space (e.g. when the window was created on another display)
+ */
+pub fn create_windows_manager_for_window<'p>(
+	plugin: &'p YabaiPlugin,
+	window: &Window,
+) -> Option<WindowsManager<'p>> {
+	let display = plugin
+		.get_displays()
+		.into_iter()
+		.find(|d| d.index == window.display)?;
+	let space = plugin
+		.get_spaces()
+		.into_iter()
+		.find(|s| s.index == window.space)?;
+
+	Some(create_windows_manager_for_space(plugin, display, space))
+}
+
+/**
+ * Creates a windows manager for a space that isn't necessarily focused
+ */
+pub fn create_windows_manager_for_space(
+	plugin: &YabaiPlugin,
+	display: Display,
+	space: Space,
+) -> WindowsManager<'_> {
+	let mut state = plugin.read_state();
+	let space_key = get_space_key(&space, std::slice::from_ref(&display));
+
+	// Spaces that were created after the state was initialized don't have an entry yet
+	let expected_current_num_master_windows = *state
+		.num_master_windows
+		.entry(space_key.clone())
+		.or_insert(1);
+	let layout = state.layouts.get(&space_key).cloned().unwrap_or_default();
+	let usable_frame = plugin.get_usable_frame(&display, &space);
 	let mut wm = WindowsManager {
 		display,
 		space,
+		space_key,
 		plugin,
 		expected_current_num_master_windows,
 		windows_data: vec![],
+		layout,
+		usable_frame,
+		classification: RefCell::new(None),
 	};
 
 	wm.initialize();
 	wm.validate_state(&mut state);
+	wm.reconcile_layout();
 
 	wm
 }
 
+#[derive(Clone, Copy)]
 pub enum GetWindowDataProps {
 	ProcessId(usize),
 	WindowId(usize),
 }
 
+impl YabaiPlugin {
+	pub fn get_windows(&self) -> Vec<Window> {
+		let output = self
+			.run_yabai_command(&YabaiCommand::Query(Query::Windows))
+			.unwrap_or_else(|e| panic!("Failed to query windows: {:#}", e));
+		serde_json::from_str(&output).expect("Failed to parse windows")
+	}
+
+	pub fn get_space_windows(&self, space: &Space) -> Vec<Window> {
+		let output = self
+			.run_yabai_command(&YabaiCommand::Query(Query::SpaceWindows(space.index)))
+			.unwrap_or_else(|e| panic!("Failed to query windows: {:#}", e));
+		serde_json::from_str(&output).expect("Failed to parse windows")
+	}
+
+	/// Finds a window on any space, including windows that aren't managed
+	pub fn get_window(&self, props: GetWindowDataProps) -> Option<Window> {
+		self.get_windows().into_iter().find(|window| match props {
+			GetWindowDataProps::ProcessId(process_id) => window.pid == process_id,
+			GetWindowDataProps::WindowId(window_id) => window.id == window_id,
+		})
+	}
+
+	/// Unlike `WindowsManager::get_focused_window`, this also returns windows that aren't managed
+	/// (e.g. floating windows)
+	pub fn get_focused_window(&self) -> Option<Window> {
+		// yabai fails the query when no window is focused
+		let output = self
+			.run_yabai_command(&YabaiCommand::Query(Query::FocusedWindow))
+			.ok()?;
+		serde_json::from_str(&output).ok()
+	}
+}
+
 impl WindowsManager<'_> {
 	pub fn get_windows_data(&self) -> Vec<Window> {
-		let output = self.plugin.run_yabai_command("-m query --windows");
-		let windows_data: Vec<Window> =
-			serde_json::from_str(&output).expect("Failed to parse windows");
-		windows_data
+		self.plugin
+			.get_space_windows(&self.space)
 			.into_iter()
-			.filter(|window| {
-				if window.floating != 0
-					|| window.display != self.display.index
-					|| window.space != self.space.index
-				{
-					return false;
-				}
-
-				if window.minimized == 1 {
-					return false;
-				}
-
-				return true;
-			})
+			.filter(|window| self.is_managed_window(window))
 			.collect()
 	}
 
+	/// A window is managed by the plugin (i.e. it is part of the master/stack layout) if it is on the
+	/// manager's space, is neither floating nor minimized, and isn't excluded by the window rules or
+	/// the window filter (e.g. dialogs)
+	pub fn is_managed_window(&self, window: &Window) -> bool {
+		window.is_tiled()
+			&& window.display == self.display.index
+			&& window.space == self.space.index
+			&& self.plugin.config.should_manage_window(window)
+	}
+
 	pub fn validate_state(&mut self, state: &mut State) {
 		if self.windows_data.len() < self.expected_current_num_master_windows {
 			self.expected_current_num_master_windows = self.windows_data.len();
-			*state.num_master_windows.get_mut(&self.space.id).unwrap() = self.windows_data.len();
+			*state.num_master_windows.get_mut(&self.space_key).unwrap() = self.windows_data.len();
 		}
 
-		if state.num_master_windows[&self.space.id] <= 0 {
-			*state.num_master_windows.get_mut(&self.space.id).unwrap() = 1;
+		if state.num_master_windows[&self.space_key] == 0 {
+			*state.num_master_windows.get_mut(&self.space_key).unwrap() = 1;
 		}
 
 		self.plugin.write_state(state);
 	}
 
 	pub fn initialize(&mut self) {
-		self.windows_data = self.get_windows_data();
+		self.refresh_windows_data();
 	}
 
 	pub fn refresh_windows_data(&mut self) {
 		let new_windows_data = self.get_windows_data();
 		self.windows_data = new_windows_data;
+		self.classification.replace(None);
 	}
 
 	pub fn get_updated_window_data(&self, window: &Window) -> Option<Window> {
 		self.windows_data
 			.iter()
 			.find(|win| window.id == win.id)
-			.map(|w| w.clone())
+			.cloned()
 	}
 
-	pub fn run_yabai_command(&mut self, command: &str) -> String {
-		let output = self.plugin.run_yabai_command(command);
+	/// Runs a command that changes the windows, and refreshes the windows data (even if the command
+	/// failed, since it might have partially taken effect)
+	pub fn run_yabai_command(&mut self, command: &YabaiCommand) -> anyhow::Result<()> {
+		let result = self.plugin.run_yabai_command(command);
 		self.refresh_windows_data();
-		return output;
-	}
-
-	pub fn get_window_data(&self, props: GetWindowDataProps) -> Window {
-		let mut windows_iterator = self.windows_data.iter();
-		match props {
-			GetWindowDataProps::ProcessId(process_id) => windows_iterator
-				.find(|window| window.pid == process_id)
-				.map(|w| w.clone())
-				.expect(&format!("Window with pid {} not found.", process_id)),
-			GetWindowDataProps::WindowId(window_id) => windows_iterator
-				.find(|window| window.id == window_id)
-				.map(|w| w.clone())
-				.expect(&format!("Window with id {} not found", window_id)),
+		result.map(|_| ())
+	}
+
+	/// Runs commands in order without refreshing the windows data in between (for commands that don't
+	/// need to see each other's effects), and refreshes it once afterwards. All the commands are run
+	/// even if some of them fail, and the first error is returned.
+	pub fn run_yabai_commands(&mut self, commands: &[YabaiCommand]) -> anyhow::Result<()> {
+		if commands.is_empty() {
+			return Ok(());
+		}
+
+		let mut result = Ok(());
+		for command in commands {
+			if let Err(e) = self.plugin.run_yabai_command(command) {
+				if result.is_ok() {
+					result = Err(e);
+				}
+			}
 		}
+		self.refresh_windows_data();
+		result
+	}
+
+	/// Records the window order (and which window is focused) in the state
+	pub fn save_layout(&mut self) {
+		self.reconcile_layout();
+		self.layout.focused_window = self.get_focused_window().map(|w| w.id);
+		self.plugin
+			.write_layout(&self.space_key, self.layout.clone());
 	}
 
 	pub fn get_focused_window(&self) -> Option<&Window> {
@@ -125,18 +253,73 @@ impl WindowsManager<'_> {
 		focused_window
 	}
 
-	/**
-	 * There is always a line dividing the master windows from the secondary windows. To find this line,
-		* we use two master observations:
-		* 1. The top-right window is always on the right side of the dividing line.
-		* 2. If there is more than one master window, the dividing line must cross the left side of two
-		* windows
-		* Using these observations, we can loop through the windows in descending x-coordinate starting from the top-right window
-		* and for each pair of windows that share x-coordinates, we check if the num_master_windows is less
-		* than the number of windows we've iterated through, and if so, return the x-coordinate of the currently
-		* processed window
-		*/
+	/// There is always a line dividing the master windows from the secondary windows. To find this line,
+	/// we use two master observations:
+	/// 1. The top-right window is always on the right side of the dividing line.
+	/// 2. If there is more than one master window, the dividing line must cross the left side of two
+	///    windows
+	///
+	/// Using these observations, we can loop through the windows in descending x-coordinate starting from the top-right window
+	/// and for each pair of windows that share x-coordinates, we check if the num_master_windows is less
+	/// than the number of windows we've iterated through, and if so, return the x-coordinate of the currently
+	/// processed window
 	pub fn get_dividing_line_x_coordinate(&self) -> f64 {
+		self.get_classification().dividing_line_x_coordinate
+	}
+
+	/// Returns the classification of the windows data for the expected master window count, which is
+	/// only computed once per windows data snapshot (unless the master window count changes)
+	fn get_classification(&self) -> Ref<'_, WindowClassification> {
+		let num_master_windows = self.expected_current_num_master_windows;
+		let is_stale = self
+			.classification
+			.borrow()
+			.as_ref()
+			.is_none_or(|classification| classification.num_master_windows != num_master_windows);
+		if is_stale {
+			self.classification.replace(Some(self.classify_windows()));
+		}
+
+		Ref::map(self.classification.borrow(), |classification| {
+			classification
+				.as_ref()
+				.expect("The windows were just classified")
+		})
+	}
+
+	fn classify_windows(&self) -> WindowClassification {
+		// Without windows, there are no master windows to the right of the line
+		let dividing_line_x_coordinate = if self.windows_data.is_empty() {
+			self.usable_frame.right()
+		} else {
+			self.compute_dividing_line_x_coordinate()
+		};
+
+		let mut classification = WindowClassification {
+			num_master_windows: self.expected_current_num_master_windows,
+			dividing_line_x_coordinate,
+			master_windows: vec![],
+			stack_windows: vec![],
+			middle_windows: vec![],
+		};
+		for window in &self.windows_data {
+			let is_master_window = approx_ge(window.frame.left(), dividing_line_x_coordinate);
+			let is_stack_window = self.is_stack_window(window);
+			if is_master_window {
+				classification.master_windows.push(window.clone());
+			}
+			if is_stack_window {
+				classification.stack_windows.push(window.clone());
+			}
+			if !is_master_window && !is_stack_window {
+				classification.middle_windows.push(window.clone());
+			}
+		}
+
+		classification
+	}
+
+	fn compute_dividing_line_x_coordinate(&self) -> f64 {
 		let top_right_window = self.get_top_right_window().unwrap_or_else(|| {
 			panic!("get_dividing_line_x_coordinate() was called when there are no windows.");
 		});
@@ -156,7 +339,7 @@ impl WindowsManager<'_> {
 		// Get all the non-stack windows to the left of the top-right window sorted by x coordinate
 		let mut eligible_windows = non_stack_windows
 			.iter()
-			.filter(|window| window.frame.x <= top_right_window.frame.x)
+			.filter(|window| approx_le(window.frame.left(), top_right_window.frame.left()))
 			.collect::<Vec<&&Window>>();
 
 		// Sort the windows by descending order of x-coordinate
@@ -182,7 +365,7 @@ impl WindowsManager<'_> {
 		for i in 0..eligible_windows.len() - 1 {
 			let cur_window = eligible_windows[i];
 			let next_window = eligible_windows[i + 1];
-			if cur_window.frame.x == next_window.frame.x
+			if approx_eq(cur_window.frame.left(), next_window.frame.left())
 				&& num_windows_to_right_of_top_right_window + i + 2
 					>= self.expected_current_num_master_windows
 			{
@@ -192,54 +375,34 @@ impl WindowsManager<'_> {
 
 		// If a pair of windows could not be found (which means all the windows are side-by-side), just
 		// return the top-right window's x-coordinate
-		return top_right_window.frame.x;
-	}
-
-	/**
-	 * The top-left window is the window with the lowest y-coordinate and the lowest x-coordinate.
-	 */
-	pub fn get_top_left_window(&self) -> &Window {
-		let left_windows: Vec<&Window> = self
-			.windows_data
-			.iter()
-			.filter(|window| self.is_window_touching_left_edge(window))
-			.collect();
-
-		let mut top_left_window = left_windows[0];
-		for window in left_windows {
-			if window.frame.y <= top_left_window.frame.y {
-				top_left_window = window;
-			}
-		}
-
-		top_left_window
+		top_right_window.frame.x
 	}
 
 	/*
 	 * The top-right window is the rightmost window with the lowest y-coordinate.
 	 */
 	pub fn get_top_right_window(&self) -> Option<&Window> {
-		if self.windows_data.len() == 0 {
+		if self.windows_data.is_empty() {
 			return None;
 		}
 
-		let mut lowest_y_coordinate = self.windows_data[0].frame.y;
+		let mut lowest_y_coordinate = self.windows_data[0].frame.top();
 
 		for window in &self.windows_data {
-			if window.frame.y < lowest_y_coordinate {
-				lowest_y_coordinate = window.frame.y;
+			if approx_lt(window.frame.top(), lowest_y_coordinate) {
+				lowest_y_coordinate = window.frame.top();
 			}
 		}
 
 		let top_windows: Vec<&Window> = self
 			.windows_data
 			.iter()
-			.filter(|window| window.frame.y == lowest_y_coordinate)
+			.filter(|window| approx_eq(window.frame.top(), lowest_y_coordinate))
 			.collect();
 
 		let mut top_right_window = top_windows[0];
 		for window in top_windows {
-			if window.frame.x > top_right_window.frame.x {
+			if approx_gt(window.frame.left(), top_right_window.frame.left()) {
 				top_right_window = window;
 			}
 		}
@@ -254,14 +417,14 @@ impl WindowsManager<'_> {
 			match widest_stack_window {
 				None => widest_stack_window = Some(window),
 				Some(widest_window) => {
-					if window.frame.w > widest_window.frame.w {
+					if approx_gt(window.frame.w, widest_window.frame.w) {
 						widest_stack_window = Some(window)
 					}
 				}
 			}
 		}
 
-		widest_stack_window.map(|w| w.clone())
+		widest_stack_window.cloned()
 	}
 
 	pub fn get_widest_master_window(&self) -> Option<Window> {
@@ -272,160 +435,188 @@ impl WindowsManager<'_> {
 			match widest_master_window {
 				None => widest_master_window = Some(window),
 				Some(widest_window) => {
-					if window.frame.w > widest_window.frame.w {
+					if approx_gt(window.frame.w, widest_window.frame.w) {
 						widest_master_window = Some(window);
 					}
 				}
 			}
 		}
 
-		widest_master_window.map(|w| w.clone())
+		widest_master_window.cloned()
 	}
 
 	// In the event that the windows get badly rearranged and all the windows span the entire width of
 	// the screen, split the top-right window vertically and then move the windows into the split
-	pub fn create_stack(&mut self) {
+	pub fn create_stack(&mut self) -> anyhow::Result<()> {
 		log::debug!("Creating stack...");
-		let top_right_window = some_or_return!(self.get_top_right_window());
+		let top_right_window = some_or_return!(self.get_top_right_window(), Ok(()));
 		log::debug!("Top-right window: {}", top_right_window.app);
 
 		if top_right_window.split == "horizontal" {
-			self.plugin
-				.run_yabai_command(&format!("-m window {} --toggle split", top_right_window.id));
+			self.plugin.run_yabai_command(&YabaiCommand::ToggleWindow {
+				window: top_right_window.id,
+				toggle: WindowToggle::Split,
+			})?;
 		}
 
-		self.columnize_stack_windows();
+		self.columnize_stack_windows()
 	}
 
-	/**
-	 * If the top-right window has a x-coordinate of 0, or if the stack dividing
-	 * line is equal to 0, then the stack does not exist
-	 */
+	/// If the top-right window touches the left edge of the usable area, or if the stack dividing
+	/// line is on that edge, then the stack does not exist
 	pub fn does_stack_exist(&self) -> bool {
 		let top_right_window = self.get_top_right_window();
 		match top_right_window {
-			Some(window) => window.frame.x != 0f64,
+			Some(window) => !self.is_window_touching_left_edge(window),
 			None => false,
 		}
 	}
 
-	/**
-	 * Turns the stack into a column by making sure the split direction of all the stack windows
-	 * is horizontal
-	 */
-	pub fn columnize_stack_windows(&mut self) {
+	/// Turns the stack into a column by making sure the split direction of all the stack windows
+	/// is horizontal
+	pub fn columnize_stack_windows(&mut self) -> anyhow::Result<()> {
 		// In this case, we want to columnize all the windows to the left of the dividing line
 		let dividing_line_x_coordinate = self.get_dividing_line_x_coordinate();
 
 		let stack_windows: Vec<Window> = self
 			.windows_data
 			.iter()
-			.filter(|window| window.frame.x < dividing_line_x_coordinate)
-			.map(|w| w.clone())
+			.filter(|window| approx_lt(window.frame.left(), dividing_line_x_coordinate))
+			.cloned()
 			.collect();
 
+		// Toggling the split of a window toggles its parent node, which can change the split of its
+		// sibling as well, so every window is checked against the updated windows data
 		if stack_windows.len() > 1 {
 			for stack_window in stack_windows {
 				if let Some(window) = self.get_updated_window_data(&stack_window) {
 					if window.split == "vertical" {
-						self.run_yabai_command(&format!("-m window {} --toggle split", window.id));
+						self.run_yabai_command(&YabaiCommand::ToggleWindow {
+							window: window.id,
+							toggle: WindowToggle::Split,
+						})?;
 					}
 				}
 			}
 		}
+
+		Ok(())
 	}
 
-	pub fn move_window_to_stack(&mut self, window: &Window) {
+	pub fn move_window_to_stack(&mut self, window: &Window) -> anyhow::Result<()> {
 		log::debug!("Moving window {} to stack.", window.app);
 
-		self.columnize_stack_windows();
-		let window = some_or_return!(self.get_updated_window_data(window));
+		self.columnize_stack_windows()?;
+		let window = some_or_return!(self.get_updated_window_data(window), Ok(()));
 
 		// Don't do anything if the window is already a stack window
 		if self.is_stack_window(&window) {
-			return;
+			return Ok(());
 		}
 
-		// Use a small heuristic that helps prevent "glitchy" window rearrangements
-		self.run_yabai_command(&format!("-m window {} --warp west", window.id));
+		// Use a small heuristic that helps prevent "glitchy" window rearrangements (yabai rejects the
+		// warp if there is no window in that direction, which is fine)
+		self.run_yabai_command(&YabaiCommand::WarpWindow {
+			window: window.id,
+			target: WindowSelector::Direction(Direction::West),
+		})
+		.ok();
 
 		if self.windows_data.len() == 2 {
 			if window.split == "horizontal" {
-				self.run_yabai_command(&format!("-m window {} --toggle split", window.id));
+				self.run_yabai_command(&YabaiCommand::ToggleWindow {
+					window: window.id,
+					toggle: WindowToggle::Split,
+				})?;
 			}
 
-			return;
+			return Ok(());
 		}
 
 		// Find a window that's touching the left side of the screen
-		let stack_window = some_or_return!(self.get_widest_stack_window());
+		let stack_window = some_or_return!(self.get_widest_stack_window(), Ok(()));
 
 		if stack_window.id == window.id {
-			return;
+			return Ok(());
 		}
 
-		self.run_yabai_command(&format!(
-			"-m window {} --warp {}",
-			window.id, stack_window.id
-		));
-		let window = some_or_return!(self.get_updated_window_data(&window));
+		self.run_yabai_command(&YabaiCommand::WarpWindow {
+			window: window.id,
+			target: WindowSelector::Id(stack_window.id),
+		})?;
+		let window = some_or_return!(self.get_updated_window_data(&window), Ok(()));
 
 		if self.windows_data.len() == 2 {
 			if window.split == "horizontal" {
-				self.run_yabai_command(&format!("-m window {} --toggle split", window.id));
+				self.run_yabai_command(&YabaiCommand::ToggleWindow {
+					window: window.id,
+					toggle: WindowToggle::Split,
+				})?;
 			}
 		} else {
 			if window.split == "vertical" {
-				self.run_yabai_command(&format!("-m window {} --toggle split", window.id));
+				self.run_yabai_command(&YabaiCommand::ToggleWindow {
+					window: window.id,
+					toggle: WindowToggle::Split,
+				})?;
 			}
 		}
+
+		Ok(())
 	}
 
-	pub fn move_window_to_master(&mut self, window: &Window) {
+	pub fn move_window_to_master(&mut self, window: &Window) -> anyhow::Result<()> {
 		log::debug!("Moving window {} to master", window.app);
 
-		// Use a small heuristic that helps prevent "glitchy" window rearrangements
-		self.run_yabai_command(&format!("-m window {} --warp east", window.id));
+		// Use a small heuristic that helps prevent "glitchy" window rearrangements (yabai rejects the
+		// warp if there is no window in that direction, which is fine)
+		self.run_yabai_command(&YabaiCommand::WarpWindow {
+			window: window.id,
+			target: WindowSelector::Direction(Direction::East),
+		})
+		.ok();
 
 		// If the window is already a master window, then don't do anything
 		if self.is_master_window(window) {
-			return;
+			return Ok(());
 		}
 
 		// Find a window that's touching the right side of the screen
-		let master_window = some_or_return!(self.get_widest_master_window());
+		let master_window = some_or_return!(self.get_widest_master_window(), Ok(()));
 
 		if master_window.id == window.id {
-			return;
+			return Ok(());
 		}
 
-		self.run_yabai_command(&format!(
-			"-m window {} --warp {}",
-			window.id, master_window.id
-		));
+		self.run_yabai_command(&YabaiCommand::WarpWindow {
+			window: window.id,
+			target: WindowSelector::Id(master_window.id),
+		})?;
 
-		let window = some_or_return!(self.get_updated_window_data(window));
+		let window = some_or_return!(self.get_updated_window_data(window), Ok(()));
 
 		if window.split == "vertical" {
-			self.run_yabai_command(&format!("-m window {} --toggle split", window.id));
+			self.run_yabai_command(&YabaiCommand::ToggleWindow {
+				window: window.id,
+				toggle: WindowToggle::Split,
+			})?;
 		}
+
+		Ok(())
 	}
 
-	/**
-	 * A window which is to the right of the dividing line is considered a master window.
-	 */
+	/// A window which is to the right of the dividing line is considered a master window.
 	pub fn is_master_window(&self, window: &Window) -> bool {
 		let dividing_line_x_coordinate = self.get_dividing_line_x_coordinate();
-		window.frame.x >= dividing_line_x_coordinate
+		approx_ge(window.frame.left(), dividing_line_x_coordinate)
 	}
 
 	pub fn is_window_touching_left_edge(&self, window: &Window) -> bool {
-		window.frame.x == self.display.frame.x
+		approx_eq(window.frame.left(), self.usable_frame.left())
 	}
 
-	/**
-	 * If the window's frame has an x equal to the x of the display, it is a stack window
-	 */
+	/// If the window touches the left edge of the usable area (i.e. the display minus the padding), it
+	/// is a stack window
 	pub fn is_stack_window(&self, window: &Window) -> bool {
 		self.is_window_touching_left_edge(window)
 	}
@@ -435,38 +626,25 @@ impl WindowsManager<'_> {
 	}
 
 	pub fn get_middle_windows(&self) -> Vec<Window> {
-		self.windows_data
-			.iter()
-			.filter(|window| self.is_middle_window(window))
-			.map(|w| w.clone())
-			.collect()
+		self.get_classification().middle_windows.clone()
 	}
 
 	pub fn get_master_windows(&self) -> Vec<Window> {
-		let dividing_line_x_coordinate = self.get_dividing_line_x_coordinate();
-		self.windows_data
-			.iter()
-			.filter(|window| window.frame.x >= dividing_line_x_coordinate)
-			.map(|w| w.clone())
-			.collect::<Vec<Window>>()
+		self.get_classification().master_windows.clone()
 	}
 
 	pub fn get_stack_windows(&self) -> Vec<Window> {
-		self.windows_data
-			.iter()
-			.filter(|window| self.is_stack_window(window))
-			.map(|w| w.clone())
-			.collect()
+		self.get_classification().stack_windows.clone()
 	}
 
-	pub fn get_top_window<'w>(&self, windows: &Vec<Window>) -> Option<Window> {
-		if windows.len() == 0 {
+	pub fn get_top_window(&self, windows: &[Window]) -> Option<Window> {
+		if windows.is_empty() {
 			return None;
 		}
 
 		let mut top_window = &windows[0];
 		for w in windows {
-			if w.frame.y < top_window.frame.y {
+			if approx_lt(w.frame.top(), top_window.frame.top()) {
 				top_window = w;
 			}
 		}
@@ -474,20 +652,20 @@ impl WindowsManager<'_> {
 		Some(top_window.clone())
 	}
 
-	pub fn is_top_window(&self, windows: &Vec<Window>, window: &Window) -> bool {
+	pub fn is_top_window(&self, windows: &[Window], window: &Window) -> bool {
 		self.get_top_window(windows)
-			.and_then(|top_window| Some(top_window.id == window.id))
+			.map(|top_window| top_window.id == window.id)
 			.unwrap_or(false)
 	}
 
-	pub fn get_bottom_window<'w>(&self, windows: &Vec<Window>) -> Option<Window> {
-		if windows.len() == 0 {
+	pub fn get_bottom_window(&self, windows: &[Window]) -> Option<Window> {
+		if windows.is_empty() {
 			return None;
 		}
 
 		let mut bottom_window = &windows[0];
 		for w in windows {
-			if w.frame.y > bottom_window.frame.y {
+			if approx_gt(w.frame.top(), bottom_window.frame.top()) {
 				bottom_window = w;
 			}
 		}
@@ -495,9 +673,9 @@ impl WindowsManager<'_> {
 		Some(bottom_window.clone())
 	}
 
-	pub fn is_bottom_window(&self, windows: &Vec<Window>, window: &Window) -> bool {
+	pub fn is_bottom_window(&self, windows: &[Window], window: &Window) -> bool {
 		self.get_bottom_window(windows)
-			.and_then(|bottom_window| Some(bottom_window.id == window.id))
+			.map(|bottom_window| bottom_window.id == window.id)
 			.unwrap_or(false)
 	}
 
@@ -517,3 +695,65 @@ impl WindowsManager<'_> {
 		self.get_bottom_window(&self.get_master_windows())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+
+	use super::{test_fixtures::create_simulated_windows_manager, *};
+	use crate::simulated_yabai::{create_simulated_plugin, SimulatedYabai};
+
+	fn get_window_ids(windows: &[Window]) -> Vec<usize> {
+		windows.iter().map(|window| window.id).collect()
+	}
+
+	#[test]
+	fn classifies_the_windows_once_per_refresh() {
+		let yabai = Arc::new(Mutex::new(SimulatedYabai::new()));
+		let plugin = create_simulated_plugin(yabai.clone());
+		let mut wm = create_simulated_windows_manager(&yabai, &plugin, 1);
+		let num_queries = yabai.lock().unwrap().num_queries;
+
+		// The classification is cached, so looking at it again doesn't query yabai
+		assert_eq!(get_window_ids(&wm.get_master_windows()), [1]);
+		assert_eq!(get_window_ids(&wm.get_stack_windows()), [2, 3, 4, 5]);
+		assert!(wm.is_master_window(&wm.windows_data[0]));
+		assert_eq!(yabai.lock().unwrap().num_queries, num_queries);
+
+		// Swapping the master window with a stack window refreshes the windows data once, and the
+		// cached classification of the old windows data is dropped along with it
+		wm.run_yabai_command(&YabaiCommand::SwapWindow {
+			window: 1,
+			target: WindowSelector::Id(2),
+		})
+		.unwrap();
+		assert_eq!(yabai.lock().unwrap().num_queries, num_queries + 1);
+		assert_eq!(get_window_ids(&wm.get_master_windows()), [2]);
+		assert!(get_window_ids(&wm.get_stack_windows()).contains(&1));
+		assert_eq!(yabai.lock().unwrap().num_queries, num_queries + 1);
+	}
+
+	#[test]
+	fn refreshes_the_windows_data_once_per_batch_of_commands() {
+		let yabai = Arc::new(Mutex::new(SimulatedYabai::new()));
+		let plugin = create_simulated_plugin(yabai.clone());
+		let mut wm = create_simulated_windows_manager(&yabai, &plugin, 1);
+		let (num_queries, num_commands) = {
+			let yabai = yabai.lock().unwrap();
+			(yabai.num_queries, yabai.num_commands)
+		};
+
+		let split_toggles: Vec<YabaiCommand> = [2, 3, 4]
+			.into_iter()
+			.map(|window| YabaiCommand::ToggleWindow {
+				window,
+				toggle: WindowToggle::Split,
+			})
+			.collect();
+		wm.run_yabai_commands(&split_toggles).unwrap();
+
+		let yabai = yabai.lock().unwrap();
+		assert_eq!(yabai.num_commands, num_commands + 3);
+		assert_eq!(yabai.num_queries, num_queries + 1);
+	}
+}
@@ -0,0 +1,80 @@
+use crate::{
+	config::{WindowPlacement, WindowRuleAction},
+	types::Window,
+};
+
+use super::WindowsManager;
+
+impl WindowsManager<'_> {
+	/// Places a window that just joined the layout according to its window rule and the configured
+	/// new window placement
+	pub fn place_new_window(&mut self, window: &Window, target_num_master_windows: usize) {
+		match self.plugin.config.get_window_rule_action(window) {
+			Some(WindowRuleAction::AlwaysMaster) => {
+				log::debug!("Moving new window {} to master (window rule).", window.app);
+				self.move_window_to_master(window)
+					.unwrap_or_else(|e| log::warn!("{:#}", e));
+				self.layout.remove_window(window.id);
+				self.reconcile_layout();
+				return;
+			}
+			Some(WindowRuleAction::AlwaysStack) => {
+				log::debug!("Moving new window {} to stack (window rule).", window.app);
+				self.move_window_to_stack(window)
+					.unwrap_or_else(|e| log::warn!("{:#}", e));
+				self.layout.remove_window(window.id);
+				self.reconcile_layout();
+				return;
+			}
+			_ => {}
+		}
+
+		let placement = self.plugin.config.get_new_window_placement(window);
+		log::debug!("Placing new window {} ({:?}).", window.app, placement);
+
+		// The window is inserted into the window order, and `update_windows` then moves it into place.
+		// The new window has already taken focus by the time it is placed, so the previously focused
+		// window is taken from the recorded layout.
+		let focused_window = match placement {
+			WindowPlacement::AttachBelowFocused => self
+				.plugin
+				.read_layout(&self.space_key)
+				.and_then(|layout| layout.focused_window),
+			_ => None,
+		};
+		if !self.layout.attach_window(
+			window.id,
+			placement,
+			target_num_master_windows,
+			focused_window,
+		) {
+			self.place_new_window_automatically(window, target_num_master_windows);
+		}
+	}
+
+	/// The window goes to the master if the master isn't full yet, and to the stack otherwise. Its
+	/// position in the window order is wherever it ends up.
+	fn place_new_window_automatically(
+		&mut self,
+		window: &Window,
+		target_num_master_windows: usize,
+	) {
+		let cur_num_master_windows = self.get_master_windows().len();
+
+		if cur_num_master_windows > 1 && cur_num_master_windows <= target_num_master_windows {
+			// move the window to the master
+			log::debug!("Moving new window {} to master.", window.app);
+			self.move_window_to_master(window)
+				.unwrap_or_else(|e| log::warn!("{:#}", e));
+		}
+		// if there are too many windows on the master
+		else {
+			log::debug!("Moving new window {} to stack.", window.app);
+			self.move_window_to_stack(window)
+				.unwrap_or_else(|e| log::warn!("{:#}", e));
+		}
+
+		self.layout.remove_window(window.id);
+		self.reconcile_layout();
+	}
+}
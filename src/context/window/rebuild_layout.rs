@@ -0,0 +1,78 @@
+use crate::context::yabai::{Direction, WindowSelector, WindowToggle, YabaiCommand};
+
+use super::WindowsManager;
+
+impl WindowsManager<'_> {
+	/// Builds the master/stack layout from scratch instead of repairing it: all managed windows except
+	/// the top master window are floated (which removes them from the BSP tree), and are then tiled
+	/// again one at a time in the window order. The master column ends up on the right and the stack
+	/// column on the left, each from top to bottom. The focused window stays focused.
+	pub fn rebuild_layout(&mut self, target_num_master_windows: usize) -> anyhow::Result<()> {
+		self.reconcile_layout();
+		let windows = self.layout.windows.clone();
+		if windows.is_empty() {
+			return Ok(());
+		}
+
+		log::debug!(
+			"Rebuilding the layout from the window order {:?}...",
+			windows
+		);
+
+		let focused_window_id = self.plugin.get_focused_window().map(|w| w.id);
+		let num_master_windows = target_num_master_windows.clamp(1, windows.len());
+		let (master_windows, stack_windows) = windows.split_at(num_master_windows);
+
+		let float_commands: Vec<YabaiCommand> = windows[1..]
+			.iter()
+			.map(|window_id| YabaiCommand::ToggleWindow {
+				window: *window_id,
+				toggle: WindowToggle::Float,
+			})
+			.collect();
+		self.run_yabai_commands(&float_commands)?;
+
+		// The top master window is the only window left in the tree, so each window is inserted by
+		// splitting the window that comes before it in its column
+		if let Some(top_stack_window) = stack_windows.first() {
+			self.tile_window_next_to(*top_stack_window, master_windows[0], Direction::West)?;
+		}
+		for column in [master_windows, stack_windows] {
+			for pair in column.windows(2) {
+				self.tile_window_next_to(pair[1], pair[0], Direction::South)?;
+			}
+		}
+
+		self.run_yabai_command(&YabaiCommand::BalanceSpace(self.space.index))?;
+		self.expected_current_num_master_windows = num_master_windows;
+
+		if let Some(focused_window_id) = focused_window_id {
+			self.run_yabai_command(&YabaiCommand::FocusWindow(WindowSelector::Id(
+				focused_window_id,
+			)))?;
+		}
+
+		log::debug!("Finished rebuilding the layout.");
+		Ok(())
+	}
+
+	/// Tiles a floating window next to a tiled window, in the given direction. Setting the insertion
+	/// point doesn't move any windows, so both commands share a refresh.
+	fn tile_window_next_to(
+		&mut self,
+		window_id: usize,
+		target_window_id: usize,
+		direction: Direction,
+	) -> anyhow::Result<()> {
+		self.run_yabai_commands(&[
+			YabaiCommand::InsertAtWindow {
+				window: target_window_id,
+				direction,
+			},
+			YabaiCommand::ToggleWindow {
+				window: window_id,
+				toggle: WindowToggle::Float,
+			},
+		])
+	}
+}
@@ -0,0 +1,204 @@
+use crate::{
+	context::{
+		state::get_space_key,
+		yabai::{WindowSelector, YabaiCommand},
+		YabaiPlugin,
+	},
+	types::{Space, Window},
+};
+
+use super::{
+	create_windows_manager_for_space, create_windows_manager_for_window, UpdateWindowsProps,
+	WindowsManager,
+};
+
+/**
+ * Updates the layout of every space that is currently visible on one of the displays
+ */
+pub fn relayout_visible_spaces(plugin: &YabaiPlugin) {
+	relayout_visible_spaces_except(plugin, &[]);
+}
+
+/**
+ * When `manage_all_visible_spaces` is enabled, updates the layout of the visible spaces other than
+ * the one that an event was already handled on
+ */
+pub fn relayout_other_visible_spaces(plugin: &YabaiPlugin, handled_space: &Space) {
+	if plugin.config.manage_all_visible_spaces {
+		relayout_visible_spaces_except(plugin, &[handled_space.id]);
+	}
+}
+
+fn relayout_visible_spaces_except(plugin: &YabaiPlugin, excluded_space_ids: &[usize]) {
+	let displays = plugin.get_displays();
+	for space in plugin.get_spaces() {
+		if space.visible != 1 || excluded_space_ids.contains(&space.id) {
+			continue;
+		}
+
+		if let Some(display) = displays.iter().find(|d| d.index == space.display).cloned() {
+			log::debug!("Updating the layout of space {}.", space.index);
+			create_windows_manager_for_space(plugin, display, space).relayout();
+		}
+	}
+}
+
+/**
+ * Repairs the layout of the spaces that windows have left (e.g. because they were destroyed,
+ * minimized or their application was hidden). Those windows can't necessarily be queried anymore,
+ * so the affected spaces are the visible spaces whose recorded window order contains a window that
+ * yabai no longer lists there as a tiled window.
+ */
+pub fn relayout_spaces_after_windows_left(plugin: &YabaiPlugin) {
+	let windows = plugin.get_windows();
+	let displays = plugin.get_displays();
+	let state = plugin.read_state();
+
+	let mut handled_space_ids = vec![];
+	for space in plugin.get_spaces() {
+		if space.visible != 1 {
+			continue;
+		}
+
+		let has_departed_windows = state
+			.layouts
+			.get(&get_space_key(&space, &displays))
+			.is_some_and(|layout| {
+				layout.windows.iter().any(|window_id| {
+					!windows
+						.iter()
+						.any(|w| w.id == *window_id && w.space == space.index && w.is_tiled())
+				})
+			});
+		if !has_departed_windows {
+			continue;
+		}
+
+		if let Some(display) = displays.iter().find(|d| d.index == space.display).cloned() {
+			log::debug!("Windows left space {}.", space.index);
+			handled_space_ids.push(space.id);
+			create_windows_manager_for_space(plugin, display, space).relayout_after_windows_left();
+		}
+	}
+
+	if plugin.config.manage_all_visible_spaces {
+		relayout_visible_spaces_except(plugin, &handled_space_ids);
+	}
+}
+
+/**
+ * Places windows that have just joined the managed set (e.g. because they were deminimized or their
+ * application was unhidden) on the spaces that they are on, and repairs the layout of those spaces
+ */
+pub fn relayout_spaces_after_windows_entered(plugin: &YabaiPlugin, windows: &[Window]) {
+	let mut handled_space_ids = vec![];
+	for window in windows {
+		let mut wm = match create_windows_manager_for_window(plugin, window) {
+			Some(wm) if !handled_space_ids.contains(&wm.space.id) => wm,
+			_ => continue,
+		};
+
+		let window_ids: Vec<usize> = windows
+			.iter()
+			.filter(|w| w.space == window.space)
+			.map(|w| w.id)
+			.collect();
+		handled_space_ids.push(wm.space.id);
+		wm.relayout_after_windows_entered(&window_ids);
+	}
+
+	if plugin.config.manage_all_visible_spaces {
+		relayout_visible_spaces_except(plugin, &handled_space_ids);
+	}
+}
+
+impl WindowsManager<'_> {
+	/// Updates the windows to match the master window count stored in the state and records the
+	/// resulting layout
+	pub fn relayout(&mut self) {
+		let state = self.plugin.read_state();
+		self.update_windows(UpdateWindowsProps {
+			target_num_master_windows: state.num_master_windows[&self.space_key],
+		});
+		self.save_layout();
+	}
+
+	/// Repairs the layout after windows have left the managed set (e.g. because they were destroyed,
+	/// minimized or hidden). If the focused window was one of them, its neighbor is focused using the
+	/// same rules as `close_focused_window`.
+	pub fn relayout_after_windows_left(&mut self) {
+		let window_to_focus = self.get_window_to_focus_after_windows_left();
+
+		let state = self.plugin.read_state();
+		self.update_windows(UpdateWindowsProps {
+			target_num_master_windows: state.num_master_windows[&self.space_key],
+		});
+
+		if let Some(window_to_focus) = window_to_focus {
+			if self.windows_data.iter().any(|w| w.id == window_to_focus) {
+				log::debug!("Focusing on the window {}", window_to_focus);
+				self.run_yabai_command(&YabaiCommand::FocusWindow(WindowSelector::Id(
+					window_to_focus,
+				)))
+				.unwrap_or_else(|e| log::warn!("{:#}", e));
+			}
+		}
+
+		self.save_layout();
+	}
+
+	/// Places the given windows that have just joined the managed set (e.g. because they were
+	/// deminimized or their application was unhidden) and then repairs the layout
+	pub fn relayout_after_windows_entered(&mut self, window_ids: &[usize]) {
+		let state = self.plugin.read_state();
+		let layout = self.plugin.read_layout(&self.space_key).unwrap_or_default();
+
+		for window_id in window_ids {
+			// Windows that are already part of the recorded layout never left it
+			if layout.contains_window(*window_id) {
+				continue;
+			}
+
+			if let Some(window) = self
+				.windows_data
+				.iter()
+				.find(|w| w.id == *window_id)
+				.cloned()
+			{
+				log::debug!("Window {} entered the layout.", window.app);
+				self.place_new_window(&window, state.num_master_windows[&self.space_key]);
+			}
+		}
+
+		self.relayout();
+	}
+
+	/// The windows that left can no longer be queried, so their positions are taken from the layout
+	/// that was recorded before they left
+	fn get_window_to_focus_after_windows_left(&self) -> Option<usize> {
+		let mut layout = self.plugin.read_layout(&self.space_key)?;
+		let focused_window = layout.focused_window?;
+		if self.windows_data.iter().any(|w| w.id == focused_window) {
+			return None;
+		}
+
+		log::debug!("The focused window {} left the layout.", focused_window);
+
+		// Other windows that left at the same time (e.g. when an application is hidden) can't
+		// receive focus either
+		let departed_windows: Vec<usize> = layout
+			.windows
+			.iter()
+			.copied()
+			.filter(|id| *id != focused_window && !self.windows_data.iter().any(|w| w.id == *id))
+			.collect();
+		for window_id in departed_windows {
+			layout.remove_window(window_id);
+		}
+
+		layout.get_window_to_focus_after_close(
+			focused_window,
+			self.expected_current_num_master_windows,
+		)
+	}
+}
@@ -0,0 +1,47 @@
+/*!
+ * The windows managers that the window tests run against a simulated yabai
+ */
+
+use std::{cell::RefCell, sync::Mutex};
+
+use super::WindowsManager;
+use crate::{
+	context::{state::Layout, YabaiPlugin},
+	simulated_yabai::SimulatedYabai,
+	types::Frame,
+};
+
+/**
+ * Returns a windows manager for the simulated space that doesn't depend on the plugin directory
+ */
+pub fn create_simulated_windows_manager<'p>(
+	yabai: &Mutex<SimulatedYabai>,
+	plugin: &'p YabaiPlugin,
+	num_master_windows: usize,
+) -> WindowsManager<'p> {
+	let (display, space) = {
+		let yabai = yabai.lock().unwrap();
+		(
+			serde_json::from_value(yabai.get_display_json()).unwrap(),
+			serde_json::from_value(yabai.get_space_json()).unwrap(),
+		)
+	};
+	let mut wm = WindowsManager {
+		display,
+		space,
+		space_key: "test".to_string(),
+		expected_current_num_master_windows: num_master_windows,
+		plugin,
+		windows_data: vec![],
+		layout: Layout::default(),
+		usable_frame: Frame {
+			x: 0.0,
+			y: 0.0,
+			w: 1440.0,
+			h: 900.0,
+		},
+		classification: RefCell::new(None),
+	};
+	wm.initialize();
+	wm
+}
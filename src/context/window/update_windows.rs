@@ -1,4 +1,13 @@
-use crate::context::window::{CheckValidLayoutPayload, CheckValidLayoutProps};
+use serde::Serialize;
+use serde_json::json;
+use std::{cmp::Reverse, time::Instant};
+
+use crate::{
+	context::window::{CheckValidLayoutPayload, CheckValidLayoutProps},
+	metrics,
+	types::Frame,
+	utils::geometry::snap_to_grid,
+};
 
 use super::WindowsManager;
 
@@ -6,6 +15,101 @@ pub struct UpdateWindowsProps {
 	pub target_num_master_windows: usize,
 }
 
+/**
+ * The ways in which balancing the windows can fail, which are reported in the `update-windows-failed`
+ * log event
+ */
+#[derive(Serialize)]
+#[serde(tag = "failure_mode", rename_all = "kebab-case")]
+enum BalanceFailure {
+	/// A loop took more iterations than it should ever need
+	IterationBudgetExceeded { stage: &'static str, budget: usize },
+	/// A move left the windows in an arrangement that they were already in before (e.g. because
+	/// yabai refused a warp)
+	NoProgress {
+		stage: &'static str,
+		iteration: usize,
+	},
+	/// yabai rejected one of the moves
+	YabaiCommandFailed { stage: &'static str, error: String },
+	/// The moves finished but the layout is still invalid
+	InvalidLayout { reason: String },
+}
+
+impl BalanceFailure {
+	fn yabai_command_failed(stage: &'static str) -> impl FnOnce(anyhow::Error) -> Self {
+		move |e| BalanceFailure::YabaiCommandFailed {
+			stage,
+			error: format!("{:#}", e),
+		}
+	}
+}
+
+/// The frames of the managed windows, sorted by window ID
+type WindowFrames = Vec<(usize, Frame)>;
+
+/**
+ * Whether the same windows have the same frames in both arrangements, since yabai can report the
+ * frames of windows that haven't moved with sub-pixel differences
+ */
+fn window_frames_approx_eq(window_frames1: &WindowFrames, window_frames2: &WindowFrames) -> bool {
+	window_frames1.len() == window_frames2.len()
+		&& window_frames1
+			.iter()
+			.zip(window_frames2)
+			.all(|((id1, frame1), (id2, frame2))| id1 == id2 && frame1.approx_eq(frame2))
+}
+
+/**
+ * Keeps one of the balancing loops from running forever: the loop fails once it exceeds its
+ * iteration budget, or once a move leaves the windows in an arrangement that was seen before
+ */
+struct ProgressTracker {
+	stage: &'static str,
+	budget: usize,
+	iteration: usize,
+	seen_window_frames: Vec<WindowFrames>,
+}
+
+impl ProgressTracker {
+	fn new(stage: &'static str, budget: usize, window_frames: WindowFrames) -> Self {
+		Self {
+			stage,
+			budget,
+			iteration: 0,
+			seen_window_frames: vec![window_frames],
+		}
+	}
+
+	fn start_iteration(&mut self) -> Result<(), BalanceFailure> {
+		if self.iteration == self.budget {
+			return Err(BalanceFailure::IterationBudgetExceeded {
+				stage: self.stage,
+				budget: self.budget,
+			});
+		}
+
+		self.iteration += 1;
+		Ok(())
+	}
+
+	fn record_progress(&mut self, window_frames: WindowFrames) -> Result<(), BalanceFailure> {
+		if self
+			.seen_window_frames
+			.iter()
+			.any(|seen_window_frames| window_frames_approx_eq(seen_window_frames, &window_frames))
+		{
+			return Err(BalanceFailure::NoProgress {
+				stage: self.stage,
+				iteration: self.iteration,
+			});
+		}
+
+		self.seen_window_frames.push(window_frames);
+		Ok(())
+	}
+}
+
 impl WindowsManager<'_> {
 	pub fn update_windows(
 		&mut self,
@@ -17,23 +121,81 @@ impl WindowsManager<'_> {
 			"updateWindows() called with targetnum_master_windows = {}",
 			target_num_master_windows
 		);
+		let start_time = Instant::now();
 		let layout_validity = self.check_valid_layout(CheckValidLayoutProps {
 			target_num_master_windows: Some(target_num_master_windows),
 		});
 
 		if let CheckValidLayoutPayload::Failure(reason) = layout_validity {
 			log::debug!("Invalid layout detected: {}. Updating windows...", reason);
+			if let Err(failure) = self.balance_windows(target_num_master_windows) {
+				self.recover_from_balance_failure(target_num_master_windows, failure);
+			}
 		} else {
-			log::debug!("Valid layout detected; no changes were made.");
+			log::debug!("Valid layout detected.");
+		}
+
+		self.expected_current_num_master_windows = target_num_master_windows;
+
+		// The master window count is correct at this point, but the windows might still be out of order
+		if let Err(e) = self.apply_window_order() {
+			log::warn!("Failed to put the windows in order: {:#}", e);
+		}
+
+		metrics::record_update_windows(start_time.elapsed());
+	}
+
+	/// Rebuilds the layout when the windows couldn't be balanced, since the incremental moves can't fix
+	/// windows that are too mangled
+	fn recover_from_balance_failure(
+		&mut self,
+		target_num_master_windows: usize,
+		failure: BalanceFailure,
+	) {
+		log::warn!(
+			"{}",
+			json!({
+				"event": "update-windows-failed",
+				"space": self.space_key,
+				"target_num_master_windows": target_num_master_windows,
+				"num_windows": self.windows_data.len(),
+				"failure": failure,
+				"recovery": "rebuild-layout",
+			})
+		);
+		if let Err(e) = self.rebuild_layout(target_num_master_windows) {
+			log::error!("Failed to rebuild the layout: {:#}", e);
 			return;
 		}
 
+		if let CheckValidLayoutPayload::Failure(reason) =
+			self.check_valid_layout(CheckValidLayoutProps {
+				target_num_master_windows: Some(target_num_master_windows),
+			}) {
+			log::error!("The rebuilt layout is still invalid: {}", reason);
+		}
+	}
+
+	fn get_window_frames(&self) -> WindowFrames {
+		let mut window_frames: WindowFrames = self
+			.windows_data
+			.iter()
+			.map(|w| (w.id, w.frame.clone()))
+			.collect();
+		window_frames.sort_by_key(|(id, _)| *id);
+		window_frames
+	}
+
+	/// Moves windows between the master and the stack until there are exactly
+	/// `target_num_master_windows` master windows and no middle windows
+	fn balance_windows(&mut self, target_num_master_windows: usize) -> Result<(), BalanceFailure> {
 		let num_windows = self.windows_data.len();
 
 		// If the stack is supposed to exist but doesn't exist
 		if target_num_master_windows != num_windows && !self.does_stack_exist() {
 			log::debug!("Stack does not exist, creating it...");
-			self.create_stack();
+			self.create_stack()
+				.map_err(BalanceFailure::yabai_command_failed("create-stack"))?;
 		}
 
 		if num_windows > 2 {
@@ -56,46 +218,50 @@ impl WindowsManager<'_> {
 					target_num_master_windows
 				);
 				// Sort the windows from bottom to top and then right to left
-				master_windows.sort_by(|window1, window2| {
-					if window1.frame.y != window2.frame.y {
-						window1
-							.frame
-							.y
-							.partial_cmp(&window2.frame.y)
-							.expect("Failed to sort floats")
-					} else {
-						window1
-							.frame
-							.x
-							.partial_cmp(&window2.frame.x)
-							.expect("Failed to sort floats")
-					}
+				master_windows.sort_by_key(|window| {
+					(
+						snap_to_grid(window.frame.top()),
+						snap_to_grid(window.frame.left()),
+					)
 				});
 
+				let mut tracker =
+					ProgressTracker::new("master-to-stack", num_windows, self.get_window_frames());
 				while cur_num_master_windows > target_num_master_windows {
+					tracker.start_iteration()?;
 					// Remove the window with the greatest y-coordinate first
 					if let Some(master_window) = master_windows.pop() {
 						log::debug!("Moving master window {} to stack", master_window.app);
-						self.move_window_to_stack(&master_window);
+						self.move_window_to_stack(&master_window)
+							.map_err(BalanceFailure::yabai_command_failed("master-to-stack"))?;
+						tracker.record_progress(self.get_window_frames())?;
 					}
 					cur_num_master_windows -= 1;
 				}
 			}
 
 			// If there are windows that aren't touching either the left side or the right side
-			// after the move, fill up master and then move the rest to stack
+			// after the move, fill up master and then move the rest to stack. Every middle window
+			// should only need to be moved once, but moving one window can turn another one into a
+			// middle window.
+			let mut tracker =
+				ProgressTracker::new("middle-windows", 2 * num_windows, self.get_window_frames());
 			let mut middle_windows = self.get_middle_windows();
-			while middle_windows.len() > 0 {
+			while !middle_windows.is_empty() {
+				tracker.start_iteration()?;
 				let middle_window = &middle_windows[0];
 				log::debug!("Middle window {} detected.", middle_window.app);
 				if cur_num_master_windows < target_num_master_windows {
 					log::debug!("Moving middle window {} to master.", middle_window.app);
-					self.move_window_to_master(middle_window);
+					self.move_window_to_master(middle_window)
+						.map_err(BalanceFailure::yabai_command_failed("middle-windows"))?;
 					cur_num_master_windows += 1;
 				} else {
 					log::debug!("Moving middle window {} to stack.", middle_window.app);
-					self.move_window_to_stack(middle_window);
+					self.move_window_to_stack(middle_window)
+						.map_err(BalanceFailure::yabai_command_failed("middle-windows"))?;
 				}
+				tracker.record_progress(self.get_window_frames())?;
 				middle_windows = self.get_middle_windows();
 			}
 
@@ -104,23 +270,17 @@ impl WindowsManager<'_> {
 
 			// Sort the stack windows by reverse y-coordinate and reverse x-coordinate to move the
 			// bottom-rightmost windows first
-			stack_windows.sort_by(|window1, window2| {
-				if window1.frame.x != window2.frame.x {
-					window2
-						.frame
-						.x
-						.partial_cmp(&window1.frame.x)
-						.expect("Failed to sort floats")
-				} else {
-					window2
-						.frame
-						.y
-						.partial_cmp(&window1.frame.y)
-						.expect("Failed to sort floats")
-				}
+			stack_windows.sort_by_key(|window| {
+				Reverse((
+					snap_to_grid(window.frame.left()),
+					snap_to_grid(window.frame.top()),
+				))
 			});
 
+			let mut tracker =
+				ProgressTracker::new("stack-to-master", num_windows, self.get_window_frames());
 			while cur_num_master_windows < target_num_master_windows {
+				tracker.start_iteration()?;
 				log::debug!(
 					"Not enough master windows ({}/{})",
 					cur_num_master_windows,
@@ -128,25 +288,124 @@ impl WindowsManager<'_> {
 				);
 				if let Some(stack_window) = stack_windows.pop() {
 					log::debug!("Moving stack window {} to master.", stack_window.app);
-					self.move_window_to_master(&stack_window);
+					self.move_window_to_master(&stack_window)
+						.map_err(BalanceFailure::yabai_command_failed("stack-to-master"))?;
+					tracker.record_progress(self.get_window_frames())?;
 				}
 				cur_num_master_windows += 1;
 			}
 		}
 
-		// Note: the following should never be called
 		if let CheckValidLayoutPayload::Failure(reason) =
 			self.check_valid_layout(CheckValidLayoutProps {
 				target_num_master_windows: Some(target_num_master_windows),
 			}) {
-			panic!(
-				"updateLayout() ended with an invalid layuot; reason: {}",
-				reason
-			)
-		} else {
-			log::debug!("updateLayout() was successful.");
+			return Err(BalanceFailure::InvalidLayout { reason });
 		}
 
-		self.expected_current_num_master_windows = target_num_master_windows;
+		log::debug!("updateLayout() was successful.");
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::{Arc, Mutex};
+
+	use super::{super::test_fixtures::create_simulated_windows_manager, *};
+	use crate::simulated_yabai::{create_simulated_plugin, Node, SimulatedYabai};
+
+	fn get_window_frames(frames: &[(f64, f64)]) -> WindowFrames {
+		frames
+			.iter()
+			.enumerate()
+			.map(|(id, (x, y))| {
+				(
+					id,
+					Frame {
+						x: *x,
+						y: *y,
+						w: 720.0,
+						h: 450.0,
+					},
+				)
+			})
+			.collect()
+	}
+
+	#[test]
+	fn fails_once_the_iteration_budget_is_exceeded() {
+		let mut tracker = ProgressTracker::new("test", 2, get_window_frames(&[(0.0, 0.0)]));
+
+		assert!(tracker.start_iteration().is_ok());
+		assert!(tracker.start_iteration().is_ok());
+		assert!(matches!(
+			tracker.start_iteration(),
+			Err(BalanceFailure::IterationBudgetExceeded { budget: 2, .. })
+		));
+	}
+
+	#[test]
+	fn fails_when_the_windows_return_to_an_arrangement_seen_before() {
+		let first_frames = get_window_frames(&[(0.0, 0.0), (720.0, 0.0)]);
+		let mut tracker = ProgressTracker::new("test", 10, first_frames);
+
+		tracker.start_iteration().ok();
+		assert!(tracker
+			.record_progress(get_window_frames(&[(720.0, 0.0), (0.0, 0.0)]))
+			.is_ok());
+
+		// Sub-pixel differences don't count as progress
+		tracker.start_iteration().ok();
+		assert!(matches!(
+			tracker.record_progress(get_window_frames(&[(0.5, 0.0), (720.0, 0.25)])),
+			Err(BalanceFailure::NoProgress { iteration: 2, .. })
+		));
+	}
+
+	#[test]
+	fn rebuilds_the_layout_when_the_windows_cannot_be_balanced() {
+		// Two master windows that the simulated yabai never moves out of the master, since it ignores
+		// warps
+		let mut simulated_yabai = SimulatedYabai::with_columns(&[3, 4], &[1, 2]);
+		simulated_yabai.ignore_warps = true;
+		let yabai = Arc::new(Mutex::new(simulated_yabai));
+		let plugin = create_simulated_plugin(yabai.clone());
+		let mut wm = create_simulated_windows_manager(&yabai, &plugin, 2);
+
+		wm.update_windows(UpdateWindowsProps {
+			target_num_master_windows: 1,
+		});
+
+		let commands = &yabai.lock().unwrap().commands;
+		assert!(commands.iter().any(|command| command.contains("--warp")));
+		assert!(commands.contains(&"window 2 --toggle float".to_string()));
+		assert!(commands.contains(&"space 1 --balance".to_string()));
+	}
+
+	#[test]
+	fn arranges_windows_that_are_side_by_side_into_columns() {
+		let mut simulated_yabai = SimulatedYabai::with_tree(Some(Node::row(&[1, 2, 3, 4])), 1);
+		simulated_yabai.balance();
+		let yabai = Arc::new(Mutex::new(simulated_yabai));
+		let plugin = create_simulated_plugin(yabai.clone());
+		let mut wm = create_simulated_windows_manager(&yabai, &plugin, 1);
+
+		wm.update_windows(UpdateWindowsProps {
+			target_num_master_windows: 1,
+		});
+
+		assert!(matches!(
+			wm.check_valid_layout(CheckValidLayoutProps {
+				target_num_master_windows: Some(1),
+			}),
+			CheckValidLayoutPayload::Success
+		));
+		assert!(yabai
+			.lock()
+			.unwrap()
+			.commands
+			.iter()
+			.any(|command| command.ends_with("--toggle split")));
 	}
 }
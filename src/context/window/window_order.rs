@@ -0,0 +1,143 @@
+use crate::context::{
+	state::Layout,
+	yabai::{WindowSelector, YabaiCommand},
+};
+
+use super::WindowsManager;
+
+impl WindowsManager<'_> {
+	/// Infers the window order from the windows' geometry: the master windows from top to bottom
+	/// followed by the stack windows from top to bottom (middle windows are left out)
+	pub fn get_observed_layout(&self) -> Layout {
+		if self.windows_data.is_empty() {
+			return Layout::default();
+		}
+
+		let mut master_windows = self.get_master_windows();
+		master_windows.sort_by(|w1, w2| {
+			w1.frame
+				.y
+				.partial_cmp(&w2.frame.y)
+				.expect("Failed to sort floats")
+		});
+		let mut stack_windows = self.get_stack_windows();
+		stack_windows.sort_by(|w1, w2| {
+			w1.frame
+				.y
+				.partial_cmp(&w2.frame.y)
+				.expect("Failed to sort floats")
+		});
+
+		Layout {
+			windows: master_windows
+				.iter()
+				.chain(stack_windows.iter())
+				.map(|w| w.id)
+				.collect(),
+			focused_window: self.get_focused_window().map(|w| w.id),
+		}
+	}
+
+	/// Makes the window order contain exactly the managed windows: windows that are gone are removed,
+	/// and windows that the order doesn't know about yet are inserted where they currently are
+	pub fn reconcile_layout(&mut self) {
+		let windows_data = &self.windows_data;
+		self.layout
+			.windows
+			.retain(|id| windows_data.iter().any(|w| w.id == *id));
+
+		let observed_layout = self.get_observed_layout();
+		for (position, window_id) in observed_layout.windows.iter().enumerate() {
+			if !self.layout.contains_window(*window_id) {
+				self.layout.insert_window(*window_id, position);
+			}
+		}
+
+		// Middle windows don't have an observed position yet
+		for window in &self.windows_data {
+			if !self.layout.contains_window(window.id) {
+				self.layout.windows.push(window.id);
+			}
+		}
+	}
+
+	/// Replaces the window order with the order that the windows are currently in, for when the
+	/// windows were rearranged by the user
+	pub fn adopt_observed_window_order(&mut self) {
+		self.layout.windows = self.get_observed_layout().windows;
+		self.reconcile_layout();
+	}
+
+	/// Verifies that the windows are arranged in the window order, and swaps them into place if they
+	/// aren't. This expects the master window count to already be correct.
+	pub fn apply_window_order(&mut self) -> anyhow::Result<()> {
+		self.reconcile_layout();
+		if self.get_observed_layout().windows == self.layout.windows {
+			return Ok(());
+		}
+
+		log::debug!(
+			"The windows don't match the window order {:?}; rearranging them...",
+			self.layout.windows
+		);
+
+		let num_master_windows = self.expected_current_num_master_windows;
+		let master_windows = self.layout.get_master_windows(num_master_windows).to_vec();
+		let stack_windows = self.layout.get_stack_windows(num_master_windows).to_vec();
+
+		// Swap the windows that are in the wrong column with each other (each swap involves a
+		// different pair of windows, so the swaps don't affect each other)
+		let observed_layout = self.get_observed_layout();
+		let misplaced_master_windows: Vec<usize> = observed_layout
+			.get_master_windows(num_master_windows)
+			.iter()
+			.copied()
+			.filter(|id| !master_windows.contains(id))
+			.collect();
+		let misplaced_stack_windows: Vec<usize> = observed_layout
+			.get_stack_windows(num_master_windows)
+			.iter()
+			.copied()
+			.filter(|id| master_windows.contains(id))
+			.collect();
+		let swap_commands: Vec<YabaiCommand> = misplaced_master_windows
+			.into_iter()
+			.zip(misplaced_stack_windows)
+			.map(|(master_window, stack_window)| YabaiCommand::SwapWindow {
+				window: stack_window,
+				target: WindowSelector::Id(master_window),
+			})
+			.collect();
+		self.run_yabai_commands(&swap_commands)?;
+
+		// Then put each column in order from top to bottom
+		for (is_master_column, column) in [(true, master_windows), (false, stack_windows)] {
+			for (position, window_id) in column.iter().enumerate() {
+				let observed_layout = self.get_observed_layout();
+				let observed_column = if is_master_column {
+					observed_layout.get_master_windows(num_master_windows)
+				} else {
+					observed_layout.get_stack_windows(num_master_windows)
+				};
+
+				if let Some(observed_window_id) = observed_column.get(position) {
+					if observed_window_id != window_id && observed_column.contains(window_id) {
+						self.run_yabai_command(&YabaiCommand::SwapWindow {
+							window: *window_id,
+							target: WindowSelector::Id(*observed_window_id),
+						})?;
+					}
+				}
+			}
+		}
+
+		if self.get_observed_layout().windows != self.layout.windows {
+			log::warn!(
+				"Failed to arrange the windows in the window order {:?}.",
+				self.layout.windows
+			);
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,252 @@
+use std::fmt;
+
+mod process;
+pub use process::*;
+
+mod socket;
+pub use socket::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+	North,
+	South,
+	East,
+	West,
+}
+
+impl Direction {
+	fn as_str(&self) -> &'static str {
+		match self {
+			Direction::North => "north",
+			Direction::South => "south",
+			Direction::East => "east",
+			Direction::West => "west",
+		}
+	}
+}
+
+/**
+ * Selects the window that a command acts on or targets
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindowSelector {
+	Id(usize),
+	/// The window next to the focused window in the given direction
+	Direction(Direction),
+	/// The first window of the space's BSP tree
+	First,
+	/// The last window of the space's BSP tree
+	Last,
+}
+
+impl WindowSelector {
+	fn to_arg(self) -> String {
+		match self {
+			WindowSelector::Id(id) => id.to_string(),
+			WindowSelector::Direction(direction) => direction.as_str().to_string(),
+			WindowSelector::First => "first".to_string(),
+			WindowSelector::Last => "last".to_string(),
+		}
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WindowToggle {
+	Float,
+	/// Switches the split of the window's parent node between vertical and horizontal
+	Split,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Query {
+	Windows,
+	/// The windows on a space (selected by index)
+	SpaceWindows(usize),
+	FocusedWindow,
+	Spaces,
+	FocusedSpace,
+	Displays,
+	FocusedDisplay,
+}
+
+/**
+ * A yabai message (the arguments that follow the yabai executable). Every interaction with yabai
+ * goes through this type, so the exact commands can be logged and compared.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum YabaiCommand {
+	Query(Query),
+	/// Reads a setting from yabai's config, for a specific space if one is given
+	GetConfig {
+		space: Option<usize>,
+		setting: &'static str,
+	},
+	FocusWindow(WindowSelector),
+	/// Closes the focused window
+	CloseFocusedWindow,
+	WarpWindow {
+		window: usize,
+		target: WindowSelector,
+	},
+	SwapWindow {
+		window: usize,
+		target: WindowSelector,
+	},
+	/// Makes the next window that is tiled split the given window in the given direction
+	InsertAtWindow {
+		window: usize,
+		direction: Direction,
+	},
+	ToggleWindow {
+		window: usize,
+		toggle: WindowToggle,
+	},
+	/// Resets the split ratios of all the windows on a space (selected by index)
+	BalanceSpace(usize),
+}
+
+impl YabaiCommand {
+	/// Whether the command only reads from yabai without changing anything
+	pub fn is_query(&self) -> bool {
+		matches!(
+			self,
+			YabaiCommand::Query(_) | YabaiCommand::GetConfig { .. }
+		)
+	}
+
+	/// Whether the command can move or resize windows (which yabai reports as window_moved events)
+	pub fn moves_windows(&self) -> bool {
+		!self.is_query() && !matches!(self, YabaiCommand::FocusWindow(_))
+	}
+
+	pub fn to_args(&self) -> Vec<String> {
+		let args: Vec<String> = match self {
+			YabaiCommand::Query(query) => {
+				let query_args = match query {
+					Query::Windows => vec!["--windows".to_string()],
+					Query::SpaceWindows(space) => vec![
+						"--windows".to_string(),
+						"--space".to_string(),
+						space.to_string(),
+					],
+					Query::FocusedWindow => vec!["--windows".to_string(), "--window".to_string()],
+					Query::Spaces => vec!["--spaces".to_string()],
+					Query::FocusedSpace => vec!["--spaces".to_string(), "--space".to_string()],
+					Query::Displays => vec!["--displays".to_string()],
+					Query::FocusedDisplay => {
+						vec!["--displays".to_string(), "--display".to_string()]
+					}
+				};
+				["query".to_string()]
+					.into_iter()
+					.chain(query_args)
+					.collect()
+			}
+			YabaiCommand::GetConfig { space, setting } => {
+				let mut args = vec!["config".to_string()];
+				if let Some(space) = space {
+					args.extend(["--space".to_string(), space.to_string()]);
+				}
+				args.push(setting.to_string());
+				args
+			}
+			YabaiCommand::FocusWindow(selector) => {
+				vec![
+					"window".to_string(),
+					"--focus".to_string(),
+					selector.to_arg(),
+				]
+			}
+			YabaiCommand::CloseFocusedWindow => vec!["window".to_string(), "--close".to_string()],
+			YabaiCommand::WarpWindow { window, target } => vec![
+				"window".to_string(),
+				window.to_string(),
+				"--warp".to_string(),
+				target.to_arg(),
+			],
+			YabaiCommand::SwapWindow { window, target } => vec![
+				"window".to_string(),
+				window.to_string(),
+				"--swap".to_string(),
+				target.to_arg(),
+			],
+			YabaiCommand::InsertAtWindow { window, direction } => vec![
+				"window".to_string(),
+				window.to_string(),
+				"--insert".to_string(),
+				direction.as_str().to_string(),
+			],
+			YabaiCommand::ToggleWindow { window, toggle } => vec![
+				"window".to_string(),
+				window.to_string(),
+				"--toggle".to_string(),
+				match toggle {
+					WindowToggle::Float => "float".to_string(),
+					WindowToggle::Split => "split".to_string(),
+				},
+			],
+			YabaiCommand::BalanceSpace(space) => {
+				vec![
+					"space".to_string(),
+					space.to_string(),
+					"--balance".to_string(),
+				]
+			}
+		};
+
+		["-m".to_string()].into_iter().chain(args).collect()
+	}
+}
+
+impl fmt::Display for YabaiCommand {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.to_args().join(" "))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn renders_commands_to_args() {
+		for (command, expected) in [
+			(
+				YabaiCommand::Query(Query::FocusedWindow),
+				"-m query --windows --window",
+			),
+			(
+				YabaiCommand::Query(Query::SpaceWindows(2)),
+				"-m query --windows --space 2",
+			),
+			(
+				YabaiCommand::GetConfig {
+					space: Some(2),
+					setting: "left_padding",
+				},
+				"-m config --space 2 left_padding",
+			),
+			(
+				YabaiCommand::FocusWindow(WindowSelector::Direction(Direction::North)),
+				"-m window --focus north",
+			),
+			(
+				YabaiCommand::WarpWindow {
+					window: 12,
+					target: WindowSelector::Id(34),
+				},
+				"-m window 12 --warp 34",
+			),
+			(
+				YabaiCommand::ToggleWindow {
+					window: 12,
+					toggle: WindowToggle::Split,
+				},
+				"-m window 12 --toggle split",
+			),
+			(YabaiCommand::BalanceSpace(3), "-m space 3 --balance"),
+		] {
+			assert_eq!(command.to_string(), expected);
+		}
+	}
+}
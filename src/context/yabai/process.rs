@@ -0,0 +1,163 @@
+use anyhow::{bail, Context};
+use std::{
+	io::Read,
+	process::{Command, Stdio},
+	thread,
+	time::{Duration, Instant},
+};
+
+use super::YabaiCommand;
+
+/// How long to wait before checking again whether yabai has exited. yabai usually answers within a
+/// few milliseconds, so the interval starts short and doubles up to the maximum.
+const MIN_POLL_INTERVAL: Duration = Duration::from_micros(50);
+const MAX_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/**
+ * Reads a pipe of the child to the end on a thread of its own (yabai blocks once a pipe's buffer is
+ * full, which happens with large window queries)
+ */
+fn drain_pipe(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<Vec<u8>> {
+	thread::spawn(move || {
+		let mut output = vec![];
+		pipe.read_to_end(&mut output).ok();
+		output
+	})
+}
+
+/**
+ * Runs the yabai executable with the command's arguments (without going through a shell), and fails
+ * if yabai exits with an error or doesn't finish within the timeout
+ */
+pub fn run_yabai_process(
+	yabai_path: &str,
+	command: &YabaiCommand,
+	timeout: Duration,
+) -> anyhow::Result<String> {
+	let mut child = Command::new(yabai_path)
+		.args(command.to_args())
+		.stdin(Stdio::null())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()
+		.with_context(|| format!("Failed to run yabai {}", command))?;
+	let stdout = drain_pipe(child.stdout.take().expect("The stdout of yabai is piped"));
+	let stderr = drain_pipe(child.stderr.take().expect("The stderr of yabai is piped"));
+
+	let deadline = Instant::now() + timeout;
+	let mut poll_interval = MIN_POLL_INTERVAL;
+	let status = loop {
+		if let Some(status) = child
+			.try_wait()
+			.with_context(|| format!("Failed to wait for yabai {}", command))?
+		{
+			break status;
+		}
+
+		if Instant::now() >= deadline {
+			// The child hasn't been reaped yet, so its process ID can't have been reused
+			child.kill().ok();
+			child.wait().ok();
+			bail!("yabai {} timed out after {:?}", command, timeout);
+		}
+
+		thread::sleep(poll_interval.min(deadline.saturating_duration_since(Instant::now())));
+		poll_interval = (poll_interval * 2).min(MAX_POLL_INTERVAL);
+	};
+
+	let stdout = stdout.join().unwrap_or_default();
+	let stderr = stderr.join().unwrap_or_default();
+	if !status.success() {
+		bail!(
+			"yabai {} failed ({}): {}",
+			command,
+			status,
+			String::from_utf8_lossy(&stderr).trim()
+		);
+	}
+
+	Ok(String::from_utf8_lossy(&stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, os::unix::fs::PermissionsExt};
+
+	use super::*;
+	use crate::context::yabai::Query;
+
+	/// Writes a shell script that stands in for the yabai executable
+	fn create_fake_yabai(name: &str, script: &str) -> String {
+		let path = std::env::temp_dir()
+			.join(format!("fake-yabai-{}-{}", std::process::id(), name))
+			.to_string_lossy()
+			.to_string();
+		fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+		fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+		path
+	}
+
+	#[test]
+	fn returns_the_output_of_yabai() {
+		let yabai_path = create_fake_yabai("output", r#"echo "$@""#);
+
+		let output = run_yabai_process(
+			&yabai_path,
+			&YabaiCommand::Query(Query::Displays),
+			Duration::from_secs(5),
+		)
+		.unwrap();
+		assert_eq!(output.trim(), "-m query --displays");
+		fs::remove_file(yabai_path).ok();
+	}
+
+	#[test]
+	fn reads_output_larger_than_the_pipe_buffer() {
+		// 64 KiB is the pipe buffer size on macOS and Linux
+		let yabai_path = create_fake_yabai("large", "head -c 200000 /dev/zero | tr '\\0' 'a'");
+
+		let output = run_yabai_process(
+			&yabai_path,
+			&YabaiCommand::Query(Query::Windows),
+			Duration::from_secs(5),
+		)
+		.unwrap();
+		assert_eq!(output.len(), 200000);
+		fs::remove_file(yabai_path).ok();
+	}
+
+	#[test]
+	fn reports_the_errors_of_yabai() {
+		let yabai_path = create_fake_yabai(
+			"error",
+			"echo 'could not locate a northward managed window' >&2; exit 1",
+		);
+
+		let error = run_yabai_process(
+			&yabai_path,
+			&YabaiCommand::Query(Query::Displays),
+			Duration::from_secs(5),
+		)
+		.unwrap_err();
+		assert!(error
+			.to_string()
+			.contains("could not locate a northward managed window"));
+		fs::remove_file(yabai_path).ok();
+	}
+
+	#[test]
+	fn kills_yabai_when_it_times_out() {
+		let yabai_path = create_fake_yabai("timeout", "sleep 10");
+
+		let start_time = Instant::now();
+		let error = run_yabai_process(
+			&yabai_path,
+			&YabaiCommand::Query(Query::Displays),
+			Duration::from_millis(100),
+		)
+		.unwrap_err();
+		assert!(error.to_string().contains("timed out"));
+		assert!(start_time.elapsed() < Duration::from_secs(5));
+		fs::remove_file(yabai_path).ok();
+	}
+}
@@ -0,0 +1,163 @@
+use anyhow::{bail, Context};
+use std::{
+	io::{Read, Write},
+	net::Shutdown,
+	os::unix::net::UnixStream,
+	time::Duration,
+};
+
+use super::YabaiCommand;
+
+/// The first byte of yabai's response when a command fails, followed by the error message
+const FAILURE_MESSAGE: u8 = 0x07;
+
+/**
+ * Returns the path of the socket that yabai listens on for the current user
+ */
+pub fn get_default_yabai_socket_path() -> String {
+	format!(
+		"/tmp/yabai_{}.socket",
+		std::env::var("USER").unwrap_or_default()
+	)
+}
+
+/**
+ * Encodes a command the way yabai's CLI sends it: the length of the message as a native-endian
+ * 32-bit integer, followed by the arguments after `-m`, each terminated by a null byte, followed by
+ * another null byte
+ */
+fn encode_message(command: &YabaiCommand) -> Vec<u8> {
+	let mut body = vec![];
+	for arg in command.to_args().iter().skip(1) {
+		body.extend(arg.as_bytes());
+		body.push(0);
+	}
+	body.push(0);
+
+	let mut message = (body.len() as i32).to_ne_bytes().to_vec();
+	message.extend(body);
+	message
+}
+
+/**
+ * Sends a command to yabai over its Unix socket, which skips spawning the yabai CLI. yabai responds
+ * once the write half of the connection is closed, and closes the connection after responding.
+ */
+pub fn run_yabai_socket_command(
+	socket_path: &str,
+	command: &YabaiCommand,
+	timeout: Duration,
+) -> anyhow::Result<String> {
+	let mut stream = UnixStream::connect(socket_path)
+		.with_context(|| format!("Failed to connect to yabai's socket at {}", socket_path))?;
+	stream.set_read_timeout(Some(timeout))?;
+	stream.set_write_timeout(Some(timeout))?;
+
+	stream
+		.write_all(&encode_message(command))
+		.with_context(|| format!("Failed to send yabai {}", command))?;
+	stream.shutdown(Shutdown::Write)?;
+
+	let mut response = vec![];
+	stream
+		.read_to_end(&mut response)
+		.with_context(|| format!("Failed to read the response to yabai {}", command))?;
+
+	match response.split_first() {
+		Some((&FAILURE_MESSAGE, error)) => bail!(
+			"yabai {} failed: {}",
+			command,
+			String::from_utf8_lossy(error).trim()
+		),
+		_ => Ok(String::from_utf8(response)?),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{os::unix::net::UnixListener, thread};
+
+	use super::*;
+	use crate::context::yabai::{Query, WindowSelector};
+
+	/// Starts a stand-in for yabai's socket that answers each connection with the next recorded
+	/// response, after checking that the request is the expected one
+	fn serve_recorded_responses(
+		name: &str,
+		responses: Vec<(YabaiCommand, Vec<u8>)>,
+	) -> (String, thread::JoinHandle<()>) {
+		let socket_path = std::env::temp_dir()
+			.join(format!("yabai-test-{}-{}.socket", name, std::process::id()))
+			.to_string_lossy()
+			.to_string();
+		std::fs::remove_file(&socket_path).ok();
+		let listener = UnixListener::bind(&socket_path).unwrap();
+
+		let server = thread::spawn(move || {
+			for (expected_command, response) in responses {
+				let (mut stream, _) = listener.accept().unwrap();
+				let mut request = vec![];
+				stream.read_to_end(&mut request).unwrap();
+				assert_eq!(request, encode_message(&expected_command));
+				stream.write_all(&response).unwrap();
+			}
+		});
+
+		(socket_path, server)
+	}
+
+	#[test]
+	fn encodes_messages_like_the_yabai_cli() {
+		let message = encode_message(&YabaiCommand::Query(Query::Windows));
+
+		let mut expected = 17i32.to_ne_bytes().to_vec();
+		expected.extend(b"query\0--windows\0\0");
+		assert_eq!(message, expected);
+	}
+
+	#[test]
+	fn replays_responses_over_the_socket() {
+		let window = include_str!("../../../fixtures/yabai/versions/v7/window.json");
+		let focus = YabaiCommand::FocusWindow(WindowSelector::Id(4262));
+		let (socket_path, server) = serve_recorded_responses(
+			"replay",
+			vec![
+				(
+					YabaiCommand::Query(Query::FocusedWindow),
+					window.as_bytes().to_vec(),
+				),
+				(focus.clone(), vec![]),
+				(
+					focus.clone(),
+					[
+						&[FAILURE_MESSAGE][..],
+						b"could not locate window with the specified id\n",
+					]
+					.concat(),
+				),
+			],
+		);
+		let timeout = Duration::from_secs(5);
+
+		let output = run_yabai_socket_command(
+			&socket_path,
+			&YabaiCommand::Query(Query::FocusedWindow),
+			timeout,
+		)
+		.unwrap();
+		assert_eq!(output, window);
+
+		assert_eq!(
+			run_yabai_socket_command(&socket_path, &focus, timeout).unwrap(),
+			""
+		);
+
+		let error = run_yabai_socket_command(&socket_path, &focus, timeout).unwrap_err();
+		assert!(error
+			.to_string()
+			.ends_with("could not locate window with the specified id"));
+
+		server.join().unwrap();
+		std::fs::remove_file(&socket_path).ok();
+	}
+}
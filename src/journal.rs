@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+	any::Any,
+	collections::BTreeMap,
+	sync::Mutex,
+	time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+	config::get_plugin_dir,
+	context::{
+		yabai::{Query, YabaiCommand},
+		YabaiPlugin,
+	},
+	logging::get_invocation_id,
+	utils::rotating_file::RotatingFile,
+};
+
+/**
+ * Returns `journal.jsonl` in the journal directory, which is rotated to `journal.1.jsonl`
+ */
+fn get_journal_file(journal_dir: &str) -> RotatingFile {
+	RotatingFile::new(
+		format!("{}/journal.jsonl", journal_dir),
+		vec![format!("{}/journal.1.jsonl", journal_dir)],
+	)
+}
+
+/// The yabai commands (other than queries) that the current invocation has issued
+static ISSUED_COMMANDS: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+pub fn record_yabai_command(command: &YabaiCommand) {
+	if command.is_query() {
+		return;
+	}
+
+	if let Ok(mut issued_commands) = ISSUED_COMMANDS.lock() {
+		issued_commands.push(command.to_string());
+	}
+}
+
+/**
+ * One line of the journal, describing one invocation of the plugin
+ */
+#[derive(Deserialize, Serialize)]
+pub struct JournalEntry {
+	pub invocation_id: String,
+	/// Seconds since the Unix epoch
+	pub timestamp: u64,
+	/// The command type and value (e.g. `trigger window-created`)
+	pub command: String,
+	pub args: Vec<String>,
+	/// The `YABAI_*` environment variables that yabai passes to signals (e.g. `YABAI_WINDOW_ID`)
+	pub environment: BTreeMap<String, String>,
+	/// yabai's window query output before the command ran, or null if the query failed
+	pub windows_before: Value,
+	pub windows_after: Value,
+	pub yabai_commands: Vec<String>,
+	/// `ok`, or the panic message if the command panicked
+	pub result: String,
+	pub duration_ms: f64,
+}
+
+/**
+ * Queries the windows for the journal, which doesn't count towards the invocation's metrics
+ */
+fn take_windows_snapshot(plugin: &YabaiPlugin) -> Value {
+	plugin
+		.run_unrecorded_yabai_command(&YabaiCommand::Query(Query::Windows))
+		.ok()
+		.and_then(|output| serde_json::from_str(&output).ok())
+		.unwrap_or(Value::Null)
+}
+
+/**
+ * Records an invocation in the journal: created before the command runs (to take the first windows
+ * snapshot), and finished once the command has returned or panicked
+ */
+pub struct JournalRecorder {
+	/// The directory that the journal files are in (the plugin directory)
+	journal_dir: String,
+	timestamp: u64,
+	start_time: Instant,
+	command: String,
+	args: Vec<String>,
+	environment: BTreeMap<String, String>,
+	windows_before: Value,
+}
+
+impl JournalRecorder {
+	pub fn start(plugin: &YabaiPlugin, command: &str, args: &[String]) -> Self {
+		Self {
+			journal_dir: get_plugin_dir(),
+			timestamp: SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.map(|d| d.as_secs())
+				.unwrap_or(0),
+			start_time: Instant::now(),
+			command: command.to_string(),
+			args: args.to_vec(),
+			environment: std::env::vars()
+				.filter(|(name, _)| name.starts_with("YABAI_"))
+				.collect(),
+			windows_before: take_windows_snapshot(plugin),
+		}
+	}
+
+	pub fn finish(
+		self,
+		plugin: &YabaiPlugin,
+		result: &Result<(), Box<dyn Any + Send>>,
+		max_size: u64,
+	) {
+		let duration = self.start_time.elapsed();
+		let result = match result {
+			Ok(()) => "ok".to_string(),
+			Err(panic) => {
+				let message = panic
+					.downcast_ref::<&str>()
+					.map(|message| message.to_string())
+					.or_else(|| panic.downcast_ref::<String>().cloned())
+					.unwrap_or_default();
+				format!("panicked: {}", message)
+			}
+		};
+
+		let entry = JournalEntry {
+			invocation_id: get_invocation_id().to_string(),
+			timestamp: self.timestamp,
+			command: self.command,
+			args: self.args,
+			environment: self.environment,
+			windows_before: self.windows_before,
+			windows_after: take_windows_snapshot(plugin),
+			yabai_commands: ISSUED_COMMANDS
+				.lock()
+				.map(|mut issued_commands| std::mem::take(&mut *issued_commands))
+				.unwrap_or_default(),
+			result,
+			duration_ms: duration.as_secs_f64() * 1000.0,
+		};
+
+		if let Err(e) = write_journal_entry(&self.journal_dir, &entry, max_size) {
+			log::warn!("Failed to write to the journal: {:#}", e);
+		}
+	}
+}
+
+/**
+ * Appends an entry to the journal. Once the journal grows past `max_size` bytes, it replaces the
+ * previous journal file and a new one is started, so at most twice that size is kept around.
+ */
+fn write_journal_entry(
+	journal_dir: &str,
+	entry: &JournalEntry,
+	max_size: u64,
+) -> anyhow::Result<()> {
+	get_journal_file(journal_dir).append_json_line(entry, max_size)
+}
+
+/**
+ * Reads the journal entries, oldest first. Lines that can't be parsed (e.g. because of a crash while
+ * writing) are skipped.
+ */
+pub fn read_journal(journal_dir: &str) -> Vec<JournalEntry> {
+	get_journal_file(journal_dir).read_json_lines()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{
+		fs,
+		sync::{Arc, Mutex},
+	};
+
+	use super::*;
+	use crate::simulated_yabai::{create_simulated_plugin, SimulatedYabai};
+
+	fn create_journal_dir(name: &str) -> String {
+		let journal_dir = std::env::temp_dir()
+			.join(format!(
+				"yabai-journal-test-{}-{}",
+				name,
+				std::process::id()
+			))
+			.to_string_lossy()
+			.to_string();
+		fs::remove_dir_all(&journal_dir).ok();
+		fs::create_dir_all(&journal_dir).unwrap();
+		journal_dir
+	}
+
+	fn create_entry(command: &str) -> JournalEntry {
+		JournalEntry {
+			invocation_id: "test".to_string(),
+			timestamp: 0,
+			command: command.to_string(),
+			args: vec![],
+			environment: BTreeMap::new(),
+			windows_before: Value::Null,
+			windows_after: Value::Null,
+			yabai_commands: vec![],
+			result: "ok".to_string(),
+			duration_ms: 0.0,
+		}
+	}
+
+	fn get_commands(entries: &[JournalEntry]) -> Vec<&str> {
+		entries.iter().map(|entry| entry.command.as_str()).collect()
+	}
+
+	#[test]
+	fn rotates_the_journal_once_it_exceeds_the_max_size() {
+		let journal_dir = create_journal_dir("rotation");
+		let entry_size = serde_json::to_string(&create_entry("a")).unwrap().len() as u64 + 1;
+
+		// The journal only rotates once it is past the max size, so each file holds two entries
+		for command in ["a", "b", "c", "d", "e"] {
+			write_journal_entry(&journal_dir, &create_entry(command), entry_size).unwrap();
+		}
+
+		// The oldest entries were dropped along with the previous journal file that they were in
+		assert_eq!(get_commands(&read_journal(&journal_dir)), ["c", "d", "e"]);
+		assert_eq!(
+			fs::read_to_string(get_journal_file(&journal_dir).path)
+				.unwrap()
+				.lines()
+				.count(),
+			1
+		);
+
+		fs::remove_dir_all(&journal_dir).ok();
+	}
+
+	#[test]
+	fn reads_both_journal_files_oldest_first_and_skips_partial_lines() {
+		let journal_dir = create_journal_dir("read");
+		let line = |command: &str| serde_json::to_string(&create_entry(command)).unwrap();
+
+		let journal_file = get_journal_file(&journal_dir);
+		fs::write(
+			&journal_file.rotated_paths[0],
+			format!("{}\n{}\n", line("a"), line("b")),
+		)
+		.unwrap();
+		// A crash while writing leaves a partial line behind
+		let partial_line = &line("c")[..20];
+		fs::write(
+			&journal_file.path,
+			format!("{}\n{}\n", partial_line, line("d")),
+		)
+		.unwrap();
+
+		assert_eq!(get_commands(&read_journal(&journal_dir)), ["a", "b", "d"]);
+		let empty_journal_dir = create_journal_dir("empty");
+		assert!(read_journal(&empty_journal_dir).is_empty());
+
+		fs::remove_dir_all(&journal_dir).ok();
+		fs::remove_dir_all(&empty_journal_dir).ok();
+	}
+
+	#[test]
+	fn records_the_panic_message_when_finishing() {
+		let journal_dir = create_journal_dir("finish");
+		let plugin = create_simulated_plugin(Arc::new(Mutex::new(SimulatedYabai::new())));
+		let create_recorder = |command: &str| JournalRecorder {
+			journal_dir: journal_dir.clone(),
+			timestamp: 0,
+			start_time: Instant::now(),
+			command: command.to_string(),
+			args: vec![],
+			environment: BTreeMap::new(),
+			windows_before: Value::Null,
+		};
+
+		create_recorder("ok").finish(&plugin, &Ok(()), 1024 * 1024);
+		create_recorder("str").finish(&plugin, &Err(Box::new("Failed to focus")), 1024 * 1024);
+		create_recorder("string").finish(
+			&plugin,
+			&Err(Box::new(format!("Window {} not found", 3))),
+			1024 * 1024,
+		);
+
+		let entries = read_journal(&journal_dir);
+		let results: Vec<&str> = entries.iter().map(|entry| entry.result.as_str()).collect();
+		assert_eq!(
+			results,
+			[
+				"ok",
+				"panicked: Failed to focus",
+				"panicked: Window 3 not found"
+			]
+		);
+		assert!(entries
+			.iter()
+			.all(|entry| entry.windows_after.as_array().is_some_and(|w| w.len() == 5)));
+
+		fs::remove_dir_all(&journal_dir).ok();
+	}
+}
@@ -0,0 +1,15 @@
+pub mod commands;
+pub mod config;
+pub mod context;
+pub mod journal;
+pub mod logging;
+pub mod macros;
+pub mod metrics;
+pub mod run_commands;
+#[cfg(any(test, feature = "simulation"))]
+#[doc(hidden)]
+pub mod simulated_yabai;
+pub mod state_commands;
+pub mod trigger_commands;
+pub mod types;
+pub mod utils;
@@ -0,0 +1,149 @@
+use env_logger::{fmt::Target, Builder, WriteStyle};
+use serde_json::json;
+use std::{
+	fs,
+	io::Write,
+	sync::OnceLock,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+	config::{get_plugin_dir, LogFormat, LoggingConfig},
+	utils::rotating_file::RotatingFile,
+};
+
+/// The log file is rotated once it grows past this size
+const MAX_LOG_FILE_SIZE: u64 = 1024 * 1024;
+/// How many rotated log files (`plugin.log.1`, `plugin.log.2`, ...) are kept
+const MAX_ROTATED_LOG_FILES: usize = 3;
+
+static INVOCATION_ID: OnceLock<String> = OnceLock::new();
+
+/**
+ * Returns an ID that is unique to this invocation of the plugin, which ties together the log lines
+ * (and other records) of one invocation
+ */
+pub fn get_invocation_id() -> &'static str {
+	INVOCATION_ID.get_or_init(|| {
+		let nanos = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|d| d.as_nanos())
+			.unwrap_or(0);
+		format!("{:x}-{:x}", nanos, std::process::id())
+	})
+}
+
+/**
+ * Removes the verbosity flags that come before the command from the arguments: each `-v` raises the
+ * log level by one step from `warn` (`-v` is `info`, `-vv` is `debug` and `-vvv` is `trace`) and
+ * `-q` lowers it to errors only. Arguments after the command are left alone, since they belong to
+ * the command. Returns the verbosity (negative when quiet) along with the remaining arguments.
+ */
+pub fn take_verbosity_flags(args: Vec<String>) -> (i32, Vec<String>) {
+	let mut verbosity = 0;
+	let mut args = args.into_iter().peekable();
+	while let Some(arg) = args.peek() {
+		match arg.as_str() {
+			"-q" => verbosity = -1,
+			_ if arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| c == 'v') => {
+				verbosity += arg.len() as i32 - 1;
+			}
+			_ => break,
+		}
+		args.next();
+	}
+
+	(verbosity, args.collect())
+}
+
+/**
+ * Returns `logs/plugin.log`, which is rotated to `plugin.log.1`, `plugin.log.2` and so on
+ */
+fn get_log_file() -> RotatingFile {
+	let log_path = format!("{}/logs/plugin.log", get_plugin_dir());
+	let rotated_log_paths = (1..=MAX_ROTATED_LOG_FILES)
+		.map(|i| format!("{}.{}", log_path, i))
+		.collect();
+	RotatingFile::new(log_path, rotated_log_paths)
+}
+
+/**
+ * Sets up the logger. The log level comes from the verbosity flags if any were given, then from
+ * `RUST_LOG`, then from the config, and is `warn` otherwise.
+ */
+pub fn init_logging(config: &LoggingConfig, verbosity: i32) {
+	let mut builder = Builder::new();
+
+	match verbosity {
+		v if v < 0 => builder.parse_filters("error"),
+		1 => builder.parse_filters("info"),
+		2 => builder.parse_filters("debug"),
+		v if v > 2 => builder.parse_filters("trace"),
+		_ => match std::env::var("RUST_LOG") {
+			Ok(filters) => builder.parse_filters(&filters),
+			Err(_) => builder.parse_filters(config.level.as_deref().unwrap_or("warn")),
+		},
+	};
+
+	if config.file {
+		let log_file = get_log_file();
+		let log_path = log_file.path.clone();
+		let log_file = fs::create_dir_all(format!("{}/logs", get_plugin_dir()))
+			.and_then(|_| log_file.open_for_appending(MAX_LOG_FILE_SIZE));
+
+		match log_file {
+			Ok(log_file) => {
+				builder
+					.target(Target::Pipe(Box::new(log_file)))
+					.write_style(WriteStyle::Never);
+			}
+			Err(e) => eprintln!("Failed to open {}, logging to stderr: {}", log_path, e),
+		}
+	}
+
+	if config.format == LogFormat::Json {
+		builder.format(|buf, record| {
+			writeln!(
+				buf,
+				"{}",
+				json!({
+					"timestamp": buf.timestamp_millis().to_string(),
+					"level": record.level().to_string(),
+					"target": record.target(),
+					"invocation_id": get_invocation_id(),
+					"message": record.args().to_string(),
+				})
+			)
+		});
+	}
+
+	builder.init();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn takes_verbosity_flags_out_of_the_arguments() {
+		let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect();
+
+		assert_eq!(
+			take_verbosity_flags(args(&["-v", "run", "focus-up-window"])),
+			(1, args(&["run", "focus-up-window"]))
+		);
+		assert_eq!(
+			take_verbosity_flags(args(&["-v", "-vv", "state", "show", "--json"])),
+			(3, args(&["state", "show", "--json"]))
+		);
+		assert_eq!(
+			take_verbosity_flags(args(&["-q", "trigger", "window-moved"])),
+			(-1, args(&["trigger", "window-moved"]))
+		);
+		// Flags after the command are the command's own
+		assert_eq!(
+			take_verbosity_flags(args(&["state", "show", "-vv"])),
+			(0, args(&["state", "show", "-vv"]))
+		);
+	}
+}
@@ -1,9 +1,15 @@
 #[macro_export]
 macro_rules! some_or_return {
 	( $e:expr ) => {
-			match $e {
-					Some(x) => x,
-					None => return,
-			}
-	}
-}
\ No newline at end of file
+		match $e {
+			Some(x) => x,
+			None => return,
+		}
+	};
+	( $e:expr, $ret:expr ) => {
+		match $e {
+			Some(x) => x,
+			None => return $ret,
+		}
+	};
+}
@@ -1,39 +1,73 @@
-mod context;
-mod macros;
-mod run_commands;
-mod trigger_commands;
-mod types;
-mod utils;
-
-use std::sync::{Arc, Mutex};
+use std::{
+	panic::AssertUnwindSafe,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex,
+	},
+	time::Instant,
+};
 
-use crate::{
+use rusty_yabai_master_stack_plugin::{
+	commands::{export_journal, stats},
+	config::{get_plugin_dir, Config},
 	context::YabaiPlugin,
+	journal::JournalRecorder,
+	logging, metrics,
 	run_commands::{
 		close_focused_window, decrease_master_window_count, focus_down_window, focus_up_window,
-		increase_master_window_count,
+		increase_master_window_count, rebuild_layout, swap_down_window, swap_up_window,
+		toggle_focused_window_float,
+	},
+	state_commands::{state_export, state_gc, state_import, state_reset, state_set, state_show},
+	trigger_commands::{
+		application_hidden, application_terminated, application_visible, display_added,
+		display_moved, display_removed, mission_control_exit, space_changed, window_created,
+		window_deminimized, window_destroyed, window_focused, window_minimized, window_moved,
+		yabai_started,
 	},
-	trigger_commands::{window_created, window_moved, yabai_started},
 	utils::lock::LockManager,
 };
 
 fn main() {
-	std::env::set_var("RUST_LOG", "debug");
-	env_logger::init();
+	let (verbosity, args) = logging::take_verbosity_flags(std::env::args().skip(1).collect());
+	let config = Config::read();
+	logging::init_logging(&config.logging, verbosity);
 
-	let plugin_lock_file_path =
-		"/Users/leonzalion/code/rusty-yabai-master-stack-plugin/plugin.lock".to_string();
+	let command_type = args.first().expect("No command type given").clone();
+	// Top-level commands only read the files in the plugin directory, so they don't wait for the lock
+	// and aren't recorded in the journal or the metrics (where every look at the stats would show up)
+	match command_type.as_str() {
+		"export-journal" => return export_journal(&args[1..]),
+		"stats" => return stats(&args[1..]),
+		_ => {}
+	}
+
+	let plugin_lock_file_path = format!("{}/plugin.lock", get_plugin_dir());
 
-	let plugin = YabaiPlugin::new();
-	let command_type = std::env::args().nth(1).expect("No command type given");
-	let command_value = std::env::args().nth(2).expect("No command value given");
+	let plugin = YabaiPlugin::new(config);
+	let command_value = args.get(1).expect("No command value given").clone();
+	let command_args: Vec<String> = args.iter().skip(2).cloned().collect();
+	let command = format!("{} {}", command_type, command_value);
+	let records_metrics = plugin.config.metrics.enabled;
+	let start_time = Instant::now();
 	let lock_manager = Arc::new(Mutex::new(LockManager::new(
 		plugin_lock_file_path.to_string(),
 	)));
 
+	// Panics in the command itself are caught, and the lock is only released once the journal entry
+	// is written (which still needs yabai to take the windows snapshot)
+	let running_command = Arc::new(AtomicBool::new(false));
+	let panic_running_command = running_command.clone();
 	let panic_lock_manager = lock_manager.clone();
+	let panic_command = command.clone();
 	std::panic::set_hook(Box::new(move |e| {
-		log::debug!("{:?}", e);
+		log::error!("{}", e);
+		if panic_running_command.load(Ordering::SeqCst) {
+			return;
+		}
+		if records_metrics {
+			metrics::write_invocation_metrics(&panic_command, start_time.elapsed(), false);
+		}
 		panic_lock_manager
 			.lock()
 			.expect("Failed to get lock.")
@@ -54,22 +88,67 @@ fn main() {
 		.acquire_lock()
 		.expect("Failed to acquire lock");
 
-	match command_type.as_str() {
+	let journal_recorder = plugin
+		.config
+		.journal
+		.enabled
+		.then(|| JournalRecorder::start(&plugin, &command, &command_args));
+
+	running_command.store(true, Ordering::SeqCst);
+	let result = std::panic::catch_unwind(AssertUnwindSafe(|| match command_type.as_str() {
 		"run" => match command_value.as_str() {
 			"close-focused-window" => close_focused_window(&plugin),
 			"decrease-master-window-count" => decrease_master_window_count(&plugin),
 			"focus-down-window" => focus_down_window(&plugin),
 			"focus-up-window" => focus_up_window(&plugin),
 			"increase-master-window-count" => increase_master_window_count(&plugin),
+			"rebuild-layout" => rebuild_layout(&plugin),
+			"swap-down-window" => swap_down_window(&plugin),
+			"swap-up-window" => swap_up_window(&plugin),
+			"toggle-focused-window-float" => toggle_focused_window_float(&plugin),
 			_ => panic!("Unrecognized run command"),
 		},
+		"state" => match command_value.as_str() {
+			"export" => state_export(&plugin, &command_args),
+			"gc" => state_gc(&plugin),
+			"import" => state_import(&plugin, &command_args),
+			"reset" => state_reset(&plugin, &command_args),
+			"set" => state_set(&plugin, &command_args),
+			"show" => state_show(&plugin, &command_args),
+			_ => panic!("Unrecognized state command"),
+		},
 		"trigger" => match command_value.as_str() {
 			"yabai-started" => yabai_started(&plugin),
+			"application-hidden" => application_hidden(&plugin),
+			"application-terminated" => application_terminated(&plugin),
+			"application-visible" => application_visible(&plugin),
+			"display-added" => display_added(&plugin),
+			"display-moved" => display_moved(&plugin),
+			"display-removed" => display_removed(&plugin),
+			"mission-control-exit" => mission_control_exit(&plugin),
+			"space-changed" => space_changed(&plugin),
 			"window-created" => window_created(&plugin),
+			"window-deminimized" => window_deminimized(&plugin),
+			"window-destroyed" => window_destroyed(&plugin),
+			"window-focused" => window_focused(&plugin),
+			"window-minimized" => window_minimized(&plugin),
 			"window-moved" => window_moved(&plugin),
 			_ => panic!("Unrecognized trigger command"),
 		},
 		_ => panic!("Unrecognized command type {}", command_type),
+	}));
+
+	running_command.store(false, Ordering::SeqCst);
+
+	if plugin.moved_windows.get() {
+		plugin.record_own_window_moves();
+	}
+	if let Some(journal_recorder) = journal_recorder {
+		journal_recorder.finish(&plugin, &result, plugin.config.journal.max_size_kb * 1024);
+	}
+
+	if records_metrics {
+		metrics::write_invocation_metrics(&command, start_time.elapsed(), result.is_ok());
 	}
 
 	lock_manager
@@ -77,4 +156,9 @@ fn main() {
 		.expect("Failed to get mutex lock")
 		.release_lock(false)
 		.expect("Failed to release lock.");
+
+	// The panic was already logged by the panic hook, and resuming it doesn't run the hook again
+	if let Err(panic) = result {
+		std::panic::resume_unwind(panic);
+	}
 }
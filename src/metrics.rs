@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+use std::{
+	sync::Mutex,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{config::get_plugin_dir, utils::rotating_file::RotatingFile};
+
+/// Once the metrics file grows past this size, it replaces the previous metrics file and a new one
+/// is started, so at most twice this size is kept around
+const MAX_METRICS_FILE_SIZE: u64 = 1024 * 1024;
+
+fn get_metrics_file() -> RotatingFile {
+	RotatingFile::new(
+		format!("{}/metrics.jsonl", get_plugin_dir()),
+		vec![format!("{}/metrics.1.jsonl", get_plugin_dir())],
+	)
+}
+
+/**
+ * What the current invocation has done so far (every invocation is a separate process)
+ */
+struct Counters {
+	yabai_commands: usize,
+	yabai_command_time: Duration,
+	update_windows_calls: usize,
+	update_windows_time: Duration,
+}
+
+static COUNTERS: Mutex<Counters> = Mutex::new(Counters {
+	yabai_commands: 0,
+	yabai_command_time: Duration::ZERO,
+	update_windows_calls: 0,
+	update_windows_time: Duration::ZERO,
+});
+
+pub fn record_yabai_command(duration: Duration) {
+	if let Ok(mut counters) = COUNTERS.lock() {
+		counters.yabai_commands += 1;
+		counters.yabai_command_time += duration;
+	}
+}
+
+pub fn record_update_windows(duration: Duration) {
+	if let Ok(mut counters) = COUNTERS.lock() {
+		counters.update_windows_calls += 1;
+		counters.update_windows_time += duration;
+	}
+}
+
+/**
+ * One line of the metrics file, describing one invocation of the plugin
+ */
+#[derive(Deserialize, Serialize)]
+pub struct InvocationMetrics {
+	/// The command type and value (e.g. `trigger window-created`)
+	pub command: String,
+	/// Seconds since the Unix epoch
+	pub timestamp: u64,
+	pub duration_ms: f64,
+	pub succeeded: bool,
+	pub yabai_commands: usize,
+	pub yabai_command_ms: f64,
+	pub update_windows_calls: usize,
+	pub update_windows_ms: f64,
+}
+
+/**
+ * Appends the metrics of the current invocation to the metrics file. Failing to record metrics
+ * never fails the command, so errors are only logged.
+ */
+pub fn write_invocation_metrics(command: &str, duration: Duration, succeeded: bool) {
+	if let Err(e) = try_write_invocation_metrics(command, duration, succeeded) {
+		log::warn!("Failed to write metrics: {:#}", e);
+	}
+}
+
+fn try_write_invocation_metrics(
+	command: &str,
+	duration: Duration,
+	succeeded: bool,
+) -> anyhow::Result<()> {
+	let metrics = {
+		let counters = COUNTERS
+			.lock()
+			.map_err(|_| anyhow::Error::msg("Metrics counters are poisoned"))?;
+		InvocationMetrics {
+			command: command.to_string(),
+			timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+			duration_ms: duration.as_secs_f64() * 1000.0,
+			succeeded,
+			yabai_commands: counters.yabai_commands,
+			yabai_command_ms: counters.yabai_command_time.as_secs_f64() * 1000.0,
+			update_windows_calls: counters.update_windows_calls,
+			update_windows_ms: counters.update_windows_time.as_secs_f64() * 1000.0,
+		}
+	};
+
+	get_metrics_file().append_json_line(&metrics, MAX_METRICS_FILE_SIZE)
+}
+
+/**
+ * Reads the recorded metrics, oldest first. Lines that can't be parsed (e.g. because of a crash
+ * while writing) are skipped.
+ */
+pub fn read_metrics() -> Vec<InvocationMetrics> {
+	get_metrics_file().read_json_lines()
+}
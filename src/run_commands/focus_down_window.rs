@@ -1,10 +1,14 @@
-use crate::context::{window::create_windows_manager, YabaiPlugin};
+use crate::context::{
+	window::create_windows_manager,
+	yabai::{Direction, WindowSelector, YabaiCommand},
+	YabaiPlugin,
+};
 
 pub fn focus_down_window(plugin: &YabaiPlugin) {
 	let mut wm = create_windows_manager(plugin);
 	if let Some(focused_window) = wm.get_focused_window() {
-		if wm.is_master_window(&focused_window)
-			&& wm.is_bottom_window(&wm.get_master_windows(), &focused_window)
+		if wm.is_master_window(focused_window)
+			&& wm.is_bottom_window(&wm.get_master_windows(), focused_window)
 		{
 			// Focus on the top stack window
 			if let Some(window_to_focus) = wm
@@ -12,7 +16,10 @@ pub fn focus_down_window(plugin: &YabaiPlugin) {
 				.or_else(|| wm.get_top_master_window())
 			{
 				log::debug!("Focusing on the window {}", window_to_focus.app);
-				wm.run_yabai_command(&format!("-m window --focus {}", window_to_focus.id));
+				wm.run_yabai_command(&YabaiCommand::FocusWindow(WindowSelector::Id(
+					window_to_focus.id,
+				)))
+				.unwrap_or_else(|e| log::warn!("{:#}", e));
 			}
 		} else if wm.is_stack_window(focused_window)
 			&& wm.is_bottom_window(&wm.get_stack_windows(), focused_window)
@@ -20,14 +27,22 @@ pub fn focus_down_window(plugin: &YabaiPlugin) {
 			// Focus on the top master window
 			if let Some(window_to_focus) = wm.get_top_master_window() {
 				log::debug!("Focusing on the window {}", window_to_focus.app);
-				wm.run_yabai_command(&format!("-m window --focus {}", window_to_focus.id));
+				wm.run_yabai_command(&YabaiCommand::FocusWindow(WindowSelector::Id(
+					window_to_focus.id,
+				)))
+				.unwrap_or_else(|e| log::warn!("{:#}", e));
 			}
 		}
 		// Otherwise, just focus south
 		else {
-			wm.run_yabai_command("-m window --focus south");
+			wm.run_yabai_command(&YabaiCommand::FocusWindow(WindowSelector::Direction(
+				Direction::South,
+			)))
+			.unwrap_or_else(|e| log::warn!("{:#}", e));
 		}
 	} else {
-		wm.run_yabai_command("-m window --focus first");
+		wm.run_yabai_command(&YabaiCommand::FocusWindow(WindowSelector::First))
+			.unwrap_or_else(|e| log::warn!("{:#}", e));
 	}
+	wm.save_layout();
 }
@@ -1,11 +1,15 @@
-use crate::context::{window::create_windows_manager, YabaiPlugin};
+use crate::context::{
+	window::create_windows_manager,
+	yabai::{Direction, WindowSelector, YabaiCommand},
+	YabaiPlugin,
+};
 
 pub fn focus_up_window(plugin: &YabaiPlugin) {
 	log::debug!("focus_up_window() called");
 	let mut wm = create_windows_manager(plugin);
 	if let Some(focused_window) = wm.get_focused_window() {
-		if wm.is_master_window(&focused_window)
-			&& wm.is_top_window(&wm.get_master_windows(), &focused_window)
+		if wm.is_master_window(focused_window)
+			&& wm.is_top_window(&wm.get_master_windows(), focused_window)
 		{
 			// Focus on the top stack window
 			if let Some(window_to_focus) = wm
@@ -13,7 +17,10 @@ pub fn focus_up_window(plugin: &YabaiPlugin) {
 				.or_else(|| wm.get_bottom_master_window())
 			{
 				log::debug!("Focusing on the window {}", window_to_focus.app);
-				wm.run_yabai_command(&format!("-m window --focus {}", window_to_focus.id));
+				wm.run_yabai_command(&YabaiCommand::FocusWindow(WindowSelector::Id(
+					window_to_focus.id,
+				)))
+				.unwrap_or_else(|e| log::warn!("{:#}", e));
 			}
 		} else if wm.is_stack_window(focused_window)
 			&& wm.is_top_window(&wm.get_stack_windows(), focused_window)
@@ -21,14 +28,22 @@ pub fn focus_up_window(plugin: &YabaiPlugin) {
 			// Focus on the top master window
 			if let Some(window_to_focus) = wm.get_top_master_window() {
 				log::debug!("Focusing on the window {}", window_to_focus.app);
-				wm.run_yabai_command(&format!("-m window --focus {}", window_to_focus.id));
+				wm.run_yabai_command(&YabaiCommand::FocusWindow(WindowSelector::Id(
+					window_to_focus.id,
+				)))
+				.unwrap_or_else(|e| log::warn!("{:#}", e));
 			}
 		}
 		// Otherwise, just focus north
 		else {
-			wm.run_yabai_command("-m window --focus north");
+			wm.run_yabai_command(&YabaiCommand::FocusWindow(WindowSelector::Direction(
+				Direction::North,
+			)))
+			.unwrap_or_else(|e| log::warn!("{:#}", e));
 		}
 	} else {
-		wm.run_yabai_command("-m window --focus last");
+		wm.run_yabai_command(&YabaiCommand::FocusWindow(WindowSelector::Last))
+			.unwrap_or_else(|e| log::warn!("{:#}", e));
 	}
+	wm.save_layout();
 }
@@ -11,4 +11,16 @@ mod focus_up_window;
 pub use focus_up_window::*;
 
 mod increase_master_window_count;
-pub use increase_master_window_count::*;
\ No newline at end of file
+pub use increase_master_window_count::*;
+
+mod rebuild_layout;
+pub use rebuild_layout::*;
+
+mod swap_down_window;
+pub use swap_down_window::*;
+
+mod swap_up_window;
+pub use swap_up_window::*;
+
+mod toggle_focused_window_float;
+pub use toggle_focused_window_float::*;
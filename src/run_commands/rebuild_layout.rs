@@ -0,0 +1,9 @@
+use crate::context::{window::create_windows_manager, YabaiPlugin};
+
+pub fn rebuild_layout(plugin: &YabaiPlugin) {
+	let mut wm = create_windows_manager(plugin);
+	let state = plugin.read_state();
+	wm.rebuild_layout(state.num_master_windows[&wm.space_key])
+		.expect("Failed to rebuild the layout");
+	wm.save_layout();
+}
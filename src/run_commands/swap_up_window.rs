@@ -0,0 +1,23 @@
+use crate::{
+	context::{
+		window::{create_windows_manager, UpdateWindowsProps},
+		YabaiPlugin,
+	},
+	some_or_return,
+};
+
+/**
+ * Swaps the focused window with the window above it in the window order (see `Layout::swap_window_up`)
+ */
+pub fn swap_up_window(plugin: &YabaiPlugin) {
+	log::debug!("swap_up_window() called");
+	let mut wm = create_windows_manager(plugin);
+	let focused_window = some_or_return!(wm.get_focused_window().map(|w| w.id));
+	wm.layout.swap_window_up(focused_window);
+
+	let state = plugin.read_state();
+	wm.update_windows(UpdateWindowsProps {
+		target_num_master_windows: state.num_master_windows[&wm.space_key],
+	});
+	wm.save_layout();
+}
@@ -0,0 +1,32 @@
+use crate::{
+	context::{
+		window::create_windows_manager,
+		yabai::{WindowToggle, YabaiCommand},
+		YabaiPlugin,
+	},
+	some_or_return,
+};
+
+/**
+ * Floats or tiles the focused window, and repairs the layout that the window left or entered (yabai
+ * doesn't send a signal for float changes)
+ */
+pub fn toggle_focused_window_float(plugin: &YabaiPlugin) {
+	let mut wm = create_windows_manager(plugin);
+	// The focused window isn't part of the windows data if it is currently floating
+	let window = some_or_return!(plugin.get_focused_window());
+
+	wm.run_yabai_command(&YabaiCommand::ToggleWindow {
+		window: window.id,
+		toggle: WindowToggle::Float,
+	})
+	.expect("Failed to toggle the float of the focused window");
+
+	if wm.windows_data.iter().any(|w| w.id == window.id) {
+		log::debug!("Window {} is no longer floating.", window.app);
+		wm.relayout_after_windows_entered(&[window.id]);
+	} else {
+		log::debug!("Window {} is now floating.", window.app);
+		wm.relayout();
+	}
+}
@@ -0,0 +1,688 @@
+/*!
+ * A simulated yabai that answers over a Unix socket (see the `socket` backend), for tests and
+ * benchmarks that need to run the plugin without a yabai process. It keeps the tiled windows of a
+ * single space on a 1440x900 display in a BSP tree like yabai does, and applies the commands that
+ * the plugin sends (focus, swap, warp, insert, float and split toggles, balance and close). New
+ * splits divide their area in half until the space is balanced.
+ */
+
+use serde_json::{json, Value};
+use std::{
+	io::{Read, Write},
+	os::unix::net::UnixListener,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc, Mutex,
+	},
+	thread,
+};
+
+use crate::{
+	config::{Config, YabaiBackend},
+	context::YabaiPlugin,
+	utils::geometry::EPSILON,
+};
+
+/// The x and y coordinates, width and height of a window
+pub type SimulatedFrame = (f64, f64, f64, f64);
+
+const DISPLAY_FRAME: SimulatedFrame = (0.0, 0.0, 1440.0, 900.0);
+
+/// The first byte of yabai's response when a command fails
+const FAILURE_MESSAGE: u8 = 0x07;
+
+pub enum Node {
+	Leaf(usize),
+	Split {
+		/// Whether the children are side by side (yabai's vertical split) instead of on top of each
+		/// other
+		vertical: bool,
+		/// The share of the area that goes to the first child
+		ratio: f64,
+		/// The left or top child, followed by the right or bottom child
+		children: Box<(Node, Node)>,
+	},
+}
+
+impl Node {
+	/// Returns a column of windows from top to bottom, the way yabai tiles them when each window splits
+	/// the one above it
+	pub fn column(window_ids: &[usize]) -> Node {
+		match window_ids {
+			[] => panic!("A column needs at least one window"),
+			[window_id] => Node::Leaf(*window_id),
+			[window_id, rest @ ..] => {
+				Node::split(Node::Leaf(*window_id), Node::column(rest), false)
+			}
+		}
+	}
+
+	/// Returns a row of windows from left to right
+	pub fn row(window_ids: &[usize]) -> Node {
+		match window_ids {
+			[] => panic!("A row needs at least one window"),
+			[window_id] => Node::Leaf(*window_id),
+			[window_id, rest @ ..] => Node::split(Node::Leaf(*window_id), Node::row(rest), true),
+		}
+	}
+
+	pub fn split(first: Node, second: Node, vertical: bool) -> Node {
+		Node::Split {
+			vertical,
+			ratio: 0.5,
+			children: Box::new((first, second)),
+		}
+	}
+
+	/// Returns how many windows the node lines up along one axis: side by side for vertical splits, on
+	/// top of each other for horizontal splits
+	fn count_along(&self, along_vertical: bool) -> usize {
+		match self {
+			Node::Leaf(_) => 1,
+			Node::Split {
+				vertical, children, ..
+			} if *vertical == along_vertical => {
+				children.0.count_along(along_vertical) + children.1.count_along(along_vertical)
+			}
+			Node::Split { children, .. } => children
+				.0
+				.count_along(along_vertical)
+				.max(children.1.count_along(along_vertical)),
+		}
+	}
+
+	/// Gives every window along each axis the same share of the area, like `yabai -m space
+	/// --balance`
+	fn balance(&mut self) {
+		if let Node::Split {
+			vertical,
+			ratio,
+			children,
+		} = self
+		{
+			let first_count = children.0.count_along(*vertical) as f64;
+			let second_count = children.1.count_along(*vertical) as f64;
+			*ratio = first_count / (first_count + second_count);
+			children.0.balance();
+			children.1.balance();
+		}
+	}
+
+	/// Returns whether the two windows are the only children of the same split
+	fn are_siblings(&self, window_id1: usize, window_id2: usize) -> bool {
+		match self {
+			Node::Leaf(_) => false,
+			Node::Split { children, .. } => match &**children {
+				(Node::Leaf(id1), Node::Leaf(id2))
+					if (*id1, *id2) == (window_id1, window_id2)
+						|| (*id1, *id2) == (window_id2, window_id1) =>
+				{
+					true
+				}
+				(first, second) => {
+					first.are_siblings(window_id1, window_id2)
+						|| second.are_siblings(window_id1, window_id2)
+				}
+			},
+		}
+	}
+
+	fn contains(&self, window_id: usize) -> bool {
+		match self {
+			Node::Leaf(id) => *id == window_id,
+			Node::Split { children, .. } => {
+				children.0.contains(window_id) || children.1.contains(window_id)
+			}
+		}
+	}
+
+	/// Removes a window from the tree, replacing its parent with its sibling
+	fn remove(self, window_id: usize) -> Option<Node> {
+		match self {
+			Node::Leaf(id) if id == window_id => None,
+			Node::Leaf(_) => Some(self),
+			Node::Split {
+				vertical,
+				ratio,
+				children,
+			} => {
+				let (first, second) = *children;
+				match (first.remove(window_id), second.remove(window_id)) {
+					(Some(first), Some(second)) => Some(Node::Split {
+						vertical,
+						ratio,
+						children: Box::new((first, second)),
+					}),
+					(Some(node), None) | (None, Some(node)) => Some(node),
+					(None, None) => None,
+				}
+			}
+		}
+	}
+
+	/// Splits the target window's leaf so that the window ends up next to it on the given side
+	fn insert(self, target_window_id: usize, window_id: usize, side: Side) -> Node {
+		match self {
+			Node::Leaf(id) if id == target_window_id => {
+				let (target, window) = (Node::Leaf(id), Node::Leaf(window_id));
+				match side {
+					Side::West => Node::split(window, target, true),
+					Side::East => Node::split(target, window, true),
+					Side::North => Node::split(window, target, false),
+					Side::South => Node::split(target, window, false),
+				}
+			}
+			Node::Leaf(_) => self,
+			Node::Split {
+				vertical,
+				ratio,
+				children,
+			} => {
+				let (first, second) = *children;
+				Node::Split {
+					vertical,
+					ratio,
+					children: Box::new((
+						first.insert(target_window_id, window_id, side),
+						second.insert(target_window_id, window_id, side),
+					)),
+				}
+			}
+		}
+	}
+
+	fn swap(&mut self, window_id1: usize, window_id2: usize) {
+		match self {
+			Node::Leaf(id) if *id == window_id1 => *id = window_id2,
+			Node::Leaf(id) if *id == window_id2 => *id = window_id1,
+			Node::Leaf(_) => {}
+			Node::Split { children, .. } => {
+				children.0.swap(window_id1, window_id2);
+				children.1.swap(window_id1, window_id2);
+			}
+		}
+	}
+
+	/// Flips the split of the window's parent. Returns whether the window has a parent.
+	fn toggle_parent_split(&mut self, window_id: usize) -> bool {
+		match self {
+			Node::Leaf(_) => false,
+			Node::Split {
+				vertical, children, ..
+			} => {
+				if matches!(children.0, Node::Leaf(id) if id == window_id)
+					|| matches!(children.1, Node::Leaf(id) if id == window_id)
+				{
+					*vertical = !*vertical;
+					return true;
+				}
+				children.0.toggle_parent_split(window_id)
+					|| children.1.toggle_parent_split(window_id)
+			}
+		}
+	}
+
+	/// Returns the frame of every window, along with the split of its parent (if it has one)
+	fn get_frames(&self, frame: SimulatedFrame, parent_split: Option<bool>) -> Vec<WindowFrame> {
+		match self {
+			Node::Leaf(id) => vec![WindowFrame {
+				id: *id,
+				frame,
+				parent_split,
+			}],
+			Node::Split {
+				vertical,
+				ratio,
+				children,
+			} => {
+				let (x, y, w, h) = frame;
+				let (first_frame, second_frame) = if *vertical {
+					let first_w = w * ratio;
+					((x, y, first_w, h), (x + first_w, y, w - first_w, h))
+				} else {
+					let first_h = h * ratio;
+					((x, y, w, first_h), (x, y + first_h, w, h - first_h))
+				};
+				let mut frames = children.0.get_frames(first_frame, Some(*vertical));
+				frames.extend(children.1.get_frames(second_frame, Some(*vertical)));
+				frames
+			}
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+	North,
+	South,
+	East,
+	West,
+}
+
+impl Side {
+	fn parse(direction: &str) -> Option<Side> {
+		match direction {
+			"north" => Some(Side::North),
+			"south" => Some(Side::South),
+			"east" => Some(Side::East),
+			"west" => Some(Side::West),
+			_ => None,
+		}
+	}
+}
+
+struct WindowFrame {
+	id: usize,
+	frame: SimulatedFrame,
+	parent_split: Option<bool>,
+}
+
+#[derive(Default)]
+pub struct SimulatedYabai {
+	tree: Option<Node>,
+	floating_windows: Vec<(usize, SimulatedFrame)>,
+	focused_window: usize,
+	/// The window that the next tiled window splits, and on which side it goes
+	insertion_point: Option<(usize, Side)>,
+	/// Makes warps succeed without moving anything (like yabai does with some apps)
+	pub ignore_warps: bool,
+	/// The commands (other than queries) that were received, in yabai's argument format
+	pub commands: Vec<String>,
+	pub num_commands: usize,
+	pub num_queries: usize,
+}
+
+impl SimulatedYabai {
+	/// Returns a simulated yabai with a valid layout: window 1 is the master window and windows 2 to
+	/// 5 make up the stack
+	pub fn new() -> Self {
+		Self::with_columns(&[2, 3, 4, 5], &[1])
+	}
+
+	/// Returns a simulated yabai with the stack column on the left and the master column on the
+	/// right, each from top to bottom. The first master window is focused.
+	pub fn with_columns(stack_windows: &[usize], master_windows: &[usize]) -> Self {
+		let tree = match (stack_windows.is_empty(), master_windows.is_empty()) {
+			(true, true) => None,
+			(true, false) => Some(Node::column(master_windows)),
+			(false, true) => Some(Node::column(stack_windows)),
+			(false, false) => Some(Node::split(
+				Node::column(stack_windows),
+				Node::column(master_windows),
+				true,
+			)),
+		};
+		let mut yabai = Self::with_tree(tree, master_windows.first().copied().unwrap_or(0));
+		yabai.balance();
+		yabai
+	}
+
+	pub fn with_tree(tree: Option<Node>, focused_window: usize) -> Self {
+		Self {
+			tree,
+			focused_window,
+			..Default::default()
+		}
+	}
+
+	pub fn balance(&mut self) {
+		if let Some(tree) = self.tree.as_mut() {
+			tree.balance();
+		}
+	}
+
+	/// Opens a new window the way yabai tiles it: it splits the focused window and takes focus
+	pub fn open_window(&mut self, window_id: usize) {
+		self.tile_window(window_id);
+		self.focused_window = window_id;
+	}
+
+	fn get_window_frames(&self) -> Vec<WindowFrame> {
+		let mut frames = self
+			.tree
+			.as_ref()
+			.map(|tree| tree.get_frames(DISPLAY_FRAME, None))
+			.unwrap_or_default();
+		frames.sort_by_key(|frame| frame.id);
+		frames
+	}
+
+	fn get_tiled_frame(&self, window_id: usize) -> Option<SimulatedFrame> {
+		self.get_window_frames()
+			.into_iter()
+			.find(|frame| frame.id == window_id)
+			.map(|frame| frame.frame)
+	}
+
+	fn is_tiled(&self, window_id: usize) -> bool {
+		self.tree
+			.as_ref()
+			.is_some_and(|tree| tree.contains(window_id))
+	}
+
+	fn get_window_json(&self, window_frame: &WindowFrame, floating: bool) -> Value {
+		let (x, y, w, h) = window_frame.frame;
+		json!({
+			"id": window_frame.id, "pid": 1000 + window_frame.id, "app": "Terminal", "title": "",
+			"frame": { "x": x, "y": y, "w": w, "h": h },
+			"level": 0, "role": "AXWindow", "subrole": "AXStandardWindow", "movable": 1,
+			"resizable": 1, "display": 1, "space": 1,
+			"focused": (window_frame.id == self.focused_window) as usize,
+			"split": match window_frame.parent_split {
+				Some(true) => "vertical",
+				Some(false) => "horizontal",
+				None => "none",
+			},
+			"floating": floating as usize, "sticky": 0, "minimized": 0, "topmost": 0,
+			"opacity": 1.0, "shadow": 1, "border": 0, "stack-index": 0, "zoom-parent": 0,
+			"zoom-fullscreen": 0, "native-fullscreen": 0
+		})
+	}
+
+	fn get_windows_json(&self) -> Vec<Value> {
+		let mut windows: Vec<Value> = self
+			.get_window_frames()
+			.iter()
+			.map(|frame| self.get_window_json(frame, false))
+			.collect();
+		windows.extend(self.floating_windows.iter().map(|(id, frame)| {
+			self.get_window_json(
+				&WindowFrame {
+					id: *id,
+					frame: *frame,
+					parent_split: None,
+				},
+				true,
+			)
+		}));
+		windows
+	}
+
+	pub fn get_space_json(&self) -> Value {
+		let window_ids: Vec<usize> = self
+			.get_windows_json()
+			.iter()
+			.map(|window| window["id"].as_u64().unwrap_or(0) as usize)
+			.collect();
+		json!({
+			"id": 1, "label": "", "index": 1, "display": 1,
+			"windows": window_ids,
+			"type": "bsp", "visible": 1, "focused": 1, "native-fullscreen": 0,
+			"first-window": window_ids.first(), "last-window": window_ids.last()
+		})
+	}
+
+	pub fn get_display_json(&self) -> Value {
+		let (x, y, w, h) = DISPLAY_FRAME;
+		json!({
+			"id": 1, "uuid": "37D8832A-2D66-02CA-B9F7-8F30A301B230", "index": 1, "spaces": [1],
+			"frame": { "x": x, "y": y, "w": w, "h": h }
+		})
+	}
+
+	/// Returns the closest tiled window on the given side of a window
+	fn find_window_on_side(&self, window_id: usize, side: Side) -> Option<usize> {
+		let (x, y, w, h) = self.get_tiled_frame(window_id)?;
+		self.get_window_frames()
+			.into_iter()
+			.filter(|other| other.id != window_id)
+			.filter_map(|other| {
+				let (ox, oy, ow, oh) = other.frame;
+				let overlaps_horizontally = ox < x + w - EPSILON && x < ox + ow - EPSILON;
+				let overlaps_vertically = oy < y + h - EPSILON && y < oy + oh - EPSILON;
+				let distance = match side {
+					Side::West if overlaps_vertically && ox + ow <= x + EPSILON => x - (ox + ow),
+					Side::East if overlaps_vertically && ox >= x + w - EPSILON => ox - (x + w),
+					Side::North if overlaps_horizontally && oy + oh <= y + EPSILON => y - (oy + oh),
+					Side::South if overlaps_horizontally && oy >= y + h - EPSILON => oy - (y + h),
+					_ => return None,
+				};
+				Some((distance, other.id))
+			})
+			.min_by(|(distance1, id1), (distance2, id2)| {
+				distance1.total_cmp(distance2).then(id1.cmp(id2))
+			})
+			.map(|(_, id)| id)
+	}
+
+	/// Adds a window to the tree by splitting the insertion point, or the focused window if there is
+	/// none. Like yabai's automatic split, the window goes to the right of wide windows and below tall
+	/// ones.
+	fn tile_window(&mut self, window_id: usize) {
+		let window_frames = self.get_window_frames();
+		let Some(first_window) = window_frames.first() else {
+			self.tree = Some(Node::Leaf(window_id));
+			return;
+		};
+
+		let (target_window_id, side) = match self.insertion_point.take() {
+			Some((target_window_id, side)) if self.is_tiled(target_window_id) => {
+				(target_window_id, side)
+			}
+			_ => {
+				let target = window_frames
+					.iter()
+					.find(|frame| frame.id == self.focused_window)
+					.unwrap_or(first_window);
+				let (_, _, w, h) = target.frame;
+				(target.id, if w >= h { Side::East } else { Side::South })
+			}
+		};
+		let tree = self.tree.take().expect("The space has tiled windows");
+		self.tree = Some(tree.insert(target_window_id, window_id, side));
+	}
+
+	/// Moves a window next to another window, splitting the other window's area like a new window
+	/// would (or swaps them if they are the only two windows of their parent)
+	fn warp_window(&mut self, window_id: usize, target_window_id: usize) -> Result<(), String> {
+		if window_id == target_window_id || !self.is_tiled(target_window_id) {
+			return Err(format!("could not warp window {}", window_id));
+		}
+		if self.ignore_warps {
+			return Ok(());
+		}
+
+		let tree = self.tree.as_mut().expect("The window is tiled");
+		if tree.are_siblings(window_id, target_window_id) {
+			tree.swap(window_id, target_window_id);
+			return Ok(());
+		}
+
+		let tree = self.tree.take().expect("The window is tiled");
+		self.tree = tree.remove(window_id);
+		let focused_window = self.focused_window;
+		self.focused_window = target_window_id;
+		self.tile_window(window_id);
+		self.focused_window = focused_window;
+		Ok(())
+	}
+
+	fn toggle_float(&mut self, window_id: usize) -> Result<(), String> {
+		if let Some(position) = self
+			.floating_windows
+			.iter()
+			.position(|(id, _)| *id == window_id)
+		{
+			self.floating_windows.remove(position);
+			self.tile_window(window_id);
+			return Ok(());
+		}
+
+		let frame = self.get_tiled_frame(window_id).ok_or_else(|| {
+			format!(
+				"could not locate window with the specified id '{}'",
+				window_id
+			)
+		})?;
+		let tree = self.tree.take().expect("The window is tiled");
+		self.tree = tree.remove(window_id);
+		self.floating_windows.push((window_id, frame));
+		Ok(())
+	}
+
+	fn parse_window_id(&self, window: &str) -> Result<usize, String> {
+		window
+			.parse()
+			.ok()
+			.filter(|id| {
+				self.is_tiled(*id) || self.floating_windows.iter().any(|(other, _)| other == id)
+			})
+			.ok_or_else(|| format!("could not locate window with the specified id '{}'", window))
+	}
+
+	/// Resolves a window selector (a window ID, a direction from the focused window, `first` or
+	/// `last`)
+	fn select_window(&self, selector: &str) -> Result<usize, String> {
+		if let Some(side) = Side::parse(selector) {
+			return self
+				.find_window_on_side(self.focused_window, side)
+				.ok_or_else(|| format!("could not locate a {}ward managed window", selector));
+		}
+
+		let window_frames = self.get_window_frames();
+		match selector {
+			"first" => window_frames.first().map(|frame| frame.id),
+			"last" => window_frames.last().map(|frame| frame.id),
+			_ => return self.parse_window_id(selector),
+		}
+		.ok_or_else(|| "could not locate a managed window".to_string())
+	}
+
+	fn respond(&mut self, args: &[&str]) -> Result<String, String> {
+		if matches!(args.first(), Some(&"query" | &"config")) {
+			self.num_queries += 1;
+		} else {
+			self.num_commands += 1;
+			self.commands.push(args.join(" "));
+		}
+
+		match args {
+			["query", "--windows", "--window"] => Ok(self
+				.get_windows_json()
+				.into_iter()
+				.find(|window| window["id"] == self.focused_window)
+				.unwrap_or_default()
+				.to_string()),
+			["query", "--windows", ..] => Ok(Value::Array(self.get_windows_json()).to_string()),
+			["query", "--spaces", "--space"] => Ok(self.get_space_json().to_string()),
+			["query", "--spaces"] => Ok(json!([self.get_space_json()]).to_string()),
+			["query", "--displays", "--display"] => Ok(self.get_display_json().to_string()),
+			["query", "--displays"] => Ok(json!([self.get_display_json()]).to_string()),
+			["config", ..] => Ok("0".to_string()),
+			["window", "--focus", selector] => {
+				self.focused_window = self.select_window(selector)?;
+				Ok(String::new())
+			}
+			["window", "--close"] => {
+				let focused_window = self.focused_window;
+				self.tree = self
+					.tree
+					.take()
+					.and_then(|tree| tree.remove(focused_window));
+				self.floating_windows
+					.retain(|(id, _)| *id != focused_window);
+				Ok(String::new())
+			}
+			["window", window, "--swap", target] => {
+				let window_id = self.parse_window_id(window)?;
+				let target_window_id = self.select_window(target)?;
+				if let Some(tree) = self.tree.as_mut() {
+					tree.swap(window_id, target_window_id);
+				}
+				Ok(String::new())
+			}
+			["window", window, "--warp", target] => {
+				let window_id = self.parse_window_id(window)?;
+				let target_window_id = match Side::parse(target) {
+					Some(side) => self.find_window_on_side(window_id, side).ok_or_else(|| {
+						format!("could not locate a {}ward managed window", target)
+					})?,
+					None => self.select_window(target)?,
+				};
+				self.warp_window(window_id, target_window_id)?;
+				Ok(String::new())
+			}
+			["window", window, "--insert", direction] => {
+				let window_id = self.parse_window_id(window)?;
+				let side = Side::parse(direction)
+					.ok_or_else(|| format!("unknown direction '{}'", direction))?;
+				self.insertion_point = Some((window_id, side));
+				Ok(String::new())
+			}
+			["window", window, "--toggle", "float"] => {
+				let window_id = self.parse_window_id(window)?;
+				self.toggle_float(window_id)?;
+				Ok(String::new())
+			}
+			["window", window, "--toggle", "split"] => {
+				let window_id = self.parse_window_id(window)?;
+				let has_parent = self
+					.tree
+					.as_mut()
+					.is_some_and(|tree| tree.toggle_parent_split(window_id));
+				if !has_parent {
+					return Err("the window has no parent node to toggle".to_string());
+				}
+				Ok(String::new())
+			}
+			["space", _, "--balance"] => {
+				self.balance();
+				Ok(String::new())
+			}
+			_ => Err(format!("unknown command '{}'", args.join(" "))),
+		}
+	}
+}
+
+/// Every simulated yabai gets a socket of its own, since tests run in parallel
+static NUM_SOCKETS: AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * Serves the simulated yabai on a socket using yabai's message format: a length prefix followed by
+ * null-terminated arguments
+ */
+pub fn serve(yabai: Arc<Mutex<SimulatedYabai>>) -> String {
+	let socket_path = std::env::temp_dir()
+		.join(format!(
+			"yabai-simulation-{}-{}.socket",
+			std::process::id(),
+			NUM_SOCKETS.fetch_add(1, Ordering::Relaxed)
+		))
+		.to_string_lossy()
+		.to_string();
+	std::fs::remove_file(&socket_path).ok();
+	let listener = UnixListener::bind(&socket_path).expect("Failed to bind the socket");
+
+	thread::spawn(move || {
+		for stream in listener.incoming() {
+			let mut stream = stream.expect("Failed to accept a connection");
+			let mut request = vec![];
+			stream
+				.read_to_end(&mut request)
+				.expect("Failed to read request");
+			let message = String::from_utf8_lossy(&request[4..]).to_string();
+			let args: Vec<&str> = message.trim_end_matches('\0').split('\0').collect();
+			let response = match yabai.lock().unwrap().respond(&args) {
+				Ok(output) => output.into_bytes(),
+				Err(error) => [&[FAILURE_MESSAGE][..], error.as_bytes()].concat(),
+			};
+			stream.write_all(&response).ok();
+		}
+	});
+
+	socket_path
+}
+
+/**
+ * Returns a plugin that talks to the simulated yabai
+ */
+pub fn create_simulated_plugin(yabai: Arc<Mutex<SimulatedYabai>>) -> YabaiPlugin {
+	YabaiPlugin::with_yabai_path(
+		"yabai",
+		Config {
+			yabai_backend: YabaiBackend::Socket,
+			yabai_socket_path: Some(serve(yabai)),
+			..Default::default()
+		},
+	)
+}
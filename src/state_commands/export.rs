@@ -0,0 +1,16 @@
+use std::fs;
+
+use crate::{context::YabaiPlugin, utils::args::get_positional_args};
+
+/**
+ * Writes the state to the given file, or to stdout if no file is given
+ */
+pub fn state_export(plugin: &YabaiPlugin, args: &[String]) {
+	let state = plugin.read_state();
+	let state_str = serde_json::to_string_pretty(&state).expect("Failed to stringify state.");
+
+	match get_positional_args(args, &[]).first() {
+		Some(path) => fs::write(path, state_str).expect("Failed to write exported state"),
+		None => println!("{}", state_str),
+	}
+}
@@ -0,0 +1,11 @@
+use crate::context::YabaiPlugin;
+
+/**
+ * Removes the state of every space that doesn't currently exist. Reconciling the state on yabai and
+ * space events only removes the spaces that were destroyed on connected displays, since the spaces
+ * of an unplugged display (and labelled spaces) get their state back when they return.
+ */
+pub fn state_gc(plugin: &YabaiPlugin) {
+	log::debug!("Removing the state of spaces that no longer exist.");
+	plugin.gc_state();
+}
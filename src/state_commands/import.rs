@@ -0,0 +1,31 @@
+use std::{fs, io::Read};
+
+use crate::{context::YabaiPlugin, utils::args::get_positional_args};
+
+/**
+ * Replaces the state with a state exported by `state export`, read from the given file or from
+ * stdin. The imported state is migrated and then reconciled with the spaces on this machine. Entries
+ * of spaces that don't exist here (e.g. spaces on displays of another machine) are kept, so that they
+ * apply once a matching space exists; `state gc` removes them.
+ */
+pub fn state_import(plugin: &YabaiPlugin, args: &[String]) {
+	let state_str = match get_positional_args(args, &[]).first() {
+		Some(&path) if path != "-" => {
+			fs::read_to_string(path).expect("Failed to read the state to import")
+		}
+		_ => {
+			let mut state_str = String::new();
+			std::io::stdin()
+				.read_to_string(&mut state_str)
+				.expect("Failed to read the state to import from stdin");
+			state_str
+		}
+	};
+
+	let (mut state, _) = plugin
+		.parse_state(&state_str)
+		.expect("Failed to parse the state to import");
+	state.reconcile(&plugin.get_spaces(), &plugin.get_displays());
+	plugin.write_state(&state);
+	log::debug!("Imported state.");
+}
@@ -0,0 +1,17 @@
+mod export;
+pub use export::*;
+
+mod gc;
+pub use gc::*;
+
+mod import;
+pub use import::*;
+
+mod reset;
+pub use reset::*;
+
+mod set;
+pub use set::*;
+
+mod show;
+pub use show::*;
@@ -0,0 +1,28 @@
+use crate::{
+	context::{
+		state::{get_space_key, State},
+		YabaiPlugin,
+	},
+	utils::args::get_option_value,
+};
+
+pub fn state_reset(plugin: &YabaiPlugin, args: &[String]) {
+	match get_option_value(args, "--space") {
+		Some(selector) => {
+			let space = plugin
+				.get_space_by_selector(selector)
+				.unwrap_or_else(|| panic!("Space {} not found", selector));
+			let space_key = get_space_key(&space, &plugin.get_displays());
+
+			let mut state = plugin.read_state();
+			state.num_master_windows.insert(space_key.clone(), 1);
+			state.layouts.remove(&space_key);
+			plugin.write_state(&state);
+			log::debug!("Reset the state of space {}.", space.index);
+		}
+		None => {
+			plugin.write_state(&State::default(plugin));
+			log::debug!("Reset the state of all spaces.");
+		}
+	}
+}
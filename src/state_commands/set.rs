@@ -0,0 +1,55 @@
+use crate::{
+	context::{
+		state::get_space_key,
+		window::{create_windows_manager_for_space, UpdateWindowsProps},
+		YabaiPlugin,
+	},
+	utils::args::{get_option_value, get_positional_args},
+};
+
+pub fn state_set(plugin: &YabaiPlugin, args: &[String]) {
+	let selector =
+		get_option_value(args, "--space").expect("No space given (--space <label|index>)");
+	let space = plugin
+		.get_space_by_selector(selector)
+		.unwrap_or_else(|| panic!("Space {} not found", selector));
+
+	match get_positional_args(args, &["--space"]).as_slice() {
+		["master-count", value] => {
+			let num_master_windows: usize = value.parse().expect("Failed to parse master count");
+			if num_master_windows == 0 {
+				panic!("The master count must be at least 1");
+			}
+
+			let displays = plugin.get_displays();
+			let space_key = get_space_key(&space, &displays);
+
+			// Apply the new master count right away if the space is visible. A windows manager lowers
+			// the stored master count to the number of windows when it is created, so the count is
+			// only written afterwards.
+			if space.visible == 1 {
+				if let Some(display) = displays.into_iter().find(|d| d.index == space.display) {
+					let mut wm = create_windows_manager_for_space(plugin, display, space.clone());
+					wm.update_windows(UpdateWindowsProps {
+						target_num_master_windows: num_master_windows
+							.min(wm.windows_data.len())
+							.max(1),
+					});
+					wm.save_layout();
+				}
+			}
+
+			let mut state = plugin.read_state();
+			state
+				.num_master_windows
+				.insert(space_key, num_master_windows);
+			plugin.write_state(&state);
+			log::debug!(
+				"Set the master count of space {} to {}.",
+				space.index,
+				num_master_windows
+			);
+		}
+		_ => panic!("Unrecognized state setting"),
+	}
+}
@@ -0,0 +1,44 @@
+use crate::{
+	context::{state::get_space_key, YabaiPlugin},
+	utils::args::has_flag,
+};
+
+pub fn state_show(plugin: &YabaiPlugin, args: &[String]) {
+	let state = plugin.read_state();
+
+	if has_flag(args, "--json") {
+		println!(
+			"{}",
+			serde_json::to_string_pretty(&state).expect("Failed to stringify state.")
+		);
+		return;
+	}
+
+	let displays = plugin.get_displays();
+	for space in plugin.get_spaces() {
+		let space_key = get_space_key(&space, &displays);
+		if space.label.is_empty() {
+			println!("Space {} [{}]", space.index, space_key);
+		} else {
+			println!("Space {} \"{}\" [{}]", space.index, space.label, space_key);
+		}
+
+		let num_master_windows = state.num_master_windows.get(&space_key);
+		match num_master_windows {
+			Some(num_master_windows) => println!("  Master windows: {}", num_master_windows),
+			None => println!("  Master windows: not set"),
+		}
+
+		if let Some(layout) = state.layouts.get(&space_key) {
+			let num_master_windows = num_master_windows.copied().unwrap_or(1);
+			println!(
+				"  Master: {:?}",
+				layout.get_master_windows(num_master_windows)
+			);
+			println!(
+				"  Stack: {:?}",
+				layout.get_stack_windows(num_master_windows)
+			);
+		}
+	}
+}
@@ -0,0 +1,7 @@
+use crate::context::{window::relayout_spaces_after_windows_left, YabaiPlugin};
+
+pub fn application_terminated(plugin: &YabaiPlugin) {
+	log::debug!("Starting to handle application_terminated.");
+	relayout_spaces_after_windows_left(plugin);
+	log::debug!("Finished handling application_terminated.");
+}
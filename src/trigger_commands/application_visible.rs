@@ -0,0 +1,22 @@
+use std::env;
+
+use crate::{
+	context::{window::relayout_spaces_after_windows_entered, YabaiPlugin},
+	types::Window,
+};
+
+pub fn application_visible(plugin: &YabaiPlugin) {
+	log::debug!("Starting to handle application_visible.");
+	let process_id: usize = env::var("YABAI_PROCESS_ID")
+		.expect("YABAI_PROCESS_ID not found in environment.")
+		.parse()
+		.expect("Failed to parse process ID");
+	// The application's windows aren't necessarily on the focused space
+	let windows: Vec<Window> = plugin
+		.get_windows()
+		.into_iter()
+		.filter(|w| w.pid == process_id)
+		.collect();
+	relayout_spaces_after_windows_entered(plugin, &windows);
+	log::debug!("Finished handling application_visible.");
+}
@@ -0,0 +1,8 @@
+use crate::context::{window::relayout_visible_spaces, YabaiPlugin};
+
+pub fn display_added(plugin: &YabaiPlugin) {
+	log::debug!("Starting to handle display_added.");
+	plugin.reconcile_state();
+	relayout_visible_spaces(plugin);
+	log::debug!("Finished handling display_added.");
+}
@@ -0,0 +1,7 @@
+use crate::context::{window::relayout_visible_spaces, YabaiPlugin};
+
+pub fn display_moved(plugin: &YabaiPlugin) {
+	log::debug!("Starting to handle display_moved.");
+	relayout_visible_spaces(plugin);
+	log::debug!("Finished handling display_moved.");
+}
@@ -0,0 +1,8 @@
+use crate::context::{window::relayout_visible_spaces, YabaiPlugin};
+
+pub fn display_removed(plugin: &YabaiPlugin) {
+	log::debug!("Starting to handle display_removed.");
+	plugin.reconcile_state();
+	relayout_visible_spaces(plugin);
+	log::debug!("Finished handling display_removed.");
+}
@@ -0,0 +1,7 @@
+use crate::context::{window::relayout_visible_spaces, YabaiPlugin};
+
+pub fn mission_control_exit(plugin: &YabaiPlugin) {
+	log::debug!("Starting to handle mission_control_exit.");
+	relayout_visible_spaces(plugin);
+	log::debug!("Finished handling mission_control_exit.");
+}
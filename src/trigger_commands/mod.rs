@@ -1,6 +1,42 @@
+mod application_hidden;
+pub use application_hidden::*;
+
+mod application_terminated;
+pub use application_terminated::*;
+
+mod application_visible;
+pub use application_visible::*;
+
+mod display_added;
+pub use display_added::*;
+
+mod display_moved;
+pub use display_moved::*;
+
+mod display_removed;
+pub use display_removed::*;
+
+mod mission_control_exit;
+pub use mission_control_exit::*;
+
+mod space_changed;
+pub use space_changed::*;
+
 mod window_created;
 pub use window_created::*;
 
+mod window_deminimized;
+pub use window_deminimized::*;
+
+mod window_destroyed;
+pub use window_destroyed::*;
+
+mod window_focused;
+pub use window_focused::*;
+
+mod window_minimized;
+pub use window_minimized::*;
+
 mod window_moved;
 pub use window_moved::*;
 
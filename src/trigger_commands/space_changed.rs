@@ -0,0 +1,15 @@
+use crate::context::{
+	window::{create_windows_manager, relayout_other_visible_spaces},
+	YabaiPlugin,
+};
+
+pub fn space_changed(plugin: &YabaiPlugin) {
+	log::debug!("Starting to handle space_changed.");
+	// Spaces might have been created or destroyed since the state was last written
+	plugin.reconcile_state();
+	// The windows on the newly focused space may have changed while it wasn't visible
+	let mut wm = create_windows_manager(plugin);
+	wm.relayout();
+	relayout_other_visible_spaces(plugin, &wm.space);
+	log::debug!("Finished handling space_changed.");
+}
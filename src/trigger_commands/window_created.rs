@@ -1,16 +1,66 @@
 use std::env;
 
-use crate::context::{
-	window::{
-		create_windows_manager, CheckValidLayoutPayload, CheckValidLayoutProps, GetWindowDataProps,
-		UpdateWindowsProps,
+use crate::{
+	config::WindowRuleAction,
+	context::{
+		window::{
+			create_windows_manager_for_window, relayout_other_visible_spaces,
+			CheckValidLayoutPayload, CheckValidLayoutProps, GetWindowDataProps, UpdateWindowsProps,
+		},
+		yabai::{WindowToggle, YabaiCommand},
+		YabaiPlugin,
 	},
-	YabaiPlugin,
+	some_or_return,
 };
 
 pub fn window_created(plugin: &YabaiPlugin) {
 	log::debug!("Starting to handle window_created");
-	let mut wm = create_windows_manager(plugin);
+
+	// The window ID identifies the window exactly, while the process might have windows on other
+	// spaces
+	let window_props;
+	if let Ok(window_id) = env::var("YABAI_WINDOW_ID") {
+		window_props =
+			GetWindowDataProps::WindowId(window_id.parse().expect("Failed to parse window ID"))
+	} else if let Ok(process_id) = env::var("YABAI_PROCESS_ID") {
+		window_props =
+			GetWindowDataProps::ProcessId(process_id.parse().expect("Failed to parse process ID"))
+	} else {
+		panic!("YABAI_PROCESS_ID and YABAI_WINDOW_ID not found in environment.");
+	}
+
+	// The window isn't necessarily created on the focused space, so its layout is managed on the
+	// space that it was created on
+	let window = some_or_return!(plugin.get_window(window_props));
+	let mut wm = some_or_return!(create_windows_manager_for_window(plugin, &window));
+
+	match plugin.config.get_window_rule_action(&window) {
+		// Ignored windows are left exactly as yabai placed them and aren't part of the layout
+		Some(WindowRuleAction::Ignore) => {
+			log::debug!(
+				"Ignoring newly created window {} (window rule).",
+				window.app
+			);
+			return;
+		}
+		Some(WindowRuleAction::Float) => {
+			log::debug!(
+				"Floating newly created window {} (window rule).",
+				window.app
+			);
+			if window.floating == 0 {
+				wm.run_yabai_command(&YabaiCommand::ToggleWindow {
+					window: window.id,
+					toggle: WindowToggle::Float,
+				})
+				.unwrap_or_else(|e| log::warn!("{:#}", e));
+			}
+			wm.relayout();
+			relayout_other_visible_spaces(plugin, &wm.space);
+			return;
+		}
+		_ => {}
+	}
 
 	if let CheckValidLayoutPayload::Success = wm.check_valid_layout(CheckValidLayoutProps {
 		target_num_master_windows: None,
@@ -18,40 +68,26 @@ pub fn window_created(plugin: &YabaiPlugin) {
 		log::debug!("Valid layout detected; no changes were made.");
 	}
 
-	let cur_num_master_windows = wm.get_master_windows().len();
-
-	let window;
-	if let Ok(process_id) = env::var("YABAI_PROCESS_ID") {
-		window = wm.get_window_data(GetWindowDataProps::ProcessId(
-			process_id.parse().expect("Failed to parse process ID"),
-		))
-	} else {
-		if let Ok(window_id) = env::var("YABAI_WINDOW_ID") {
-			window = wm.get_window_data(GetWindowDataProps::WindowId(
-				window_id.parse().expect("Failed to parse window ID"),
-			))
-		} else {
-			panic!("YABAI_PROCESS_ID and YABAI_WINDOW_ID not found in environment.");
+	// Floating windows and windows excluded by the window filter (e.g. dialogs) aren't part of the
+	// layout
+	let window = match wm.get_updated_window_data(&window) {
+		Some(window) => window,
+		None => {
+			log::debug!("Newly created window {} isn't managed.", window.app);
+			return;
 		}
-	}
+	};
 
 	let state = plugin.read_state();
 
-	if cur_num_master_windows > 1 && cur_num_master_windows <= state.num_master_windows[&wm.space.id]
-	{
-		// move the window to the master
-		log::debug!("Moving newly created window to master.");
-		wm.move_window_to_master(&window);
-	}
-	// if there are too many windows on the master
-	else {
-		log::debug!("Moving newly created window to stack.");
-		wm.move_window_to_stack(&window);
-	}
+	wm.place_new_window(&window, state.num_master_windows[&wm.space_key]);
 
 	wm.update_windows(UpdateWindowsProps {
-		target_num_master_windows: state.num_master_windows[&wm.space.id],
+		target_num_master_windows: state.num_master_windows[&wm.space_key],
 	});
+	wm.save_layout();
+
+	relayout_other_visible_spaces(plugin, &wm.space);
 
 	log::debug!("Finished handling window_created");
 }
@@ -0,0 +1,20 @@
+use std::env;
+
+use crate::{
+	context::{
+		window::{relayout_spaces_after_windows_entered, GetWindowDataProps},
+		YabaiPlugin,
+	},
+	some_or_return,
+};
+
+pub fn window_deminimized(plugin: &YabaiPlugin) {
+	log::debug!("Starting to handle window_deminimized.");
+	let window_id: usize = env::var("YABAI_WINDOW_ID")
+		.expect("YABAI_WINDOW_ID not found in environment.")
+		.parse()
+		.expect("Failed to parse window ID");
+	let window = some_or_return!(plugin.get_window(GetWindowDataProps::WindowId(window_id)));
+	relayout_spaces_after_windows_entered(plugin, &[window]);
+	log::debug!("Finished handling window_deminimized.");
+}
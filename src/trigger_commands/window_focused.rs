@@ -0,0 +1,51 @@
+use std::env;
+
+use crate::{
+	context::{state::get_space_key, YabaiPlugin},
+	some_or_return,
+};
+
+/**
+ * Records which window has focus, so that the neighbor of a window that is closed or minimized can
+ * be focused even when the focus was last changed outside of the plugin (e.g. with the mouse)
+ */
+pub fn window_focused(plugin: &YabaiPlugin) {
+	log::debug!("Starting to handle window_focused.");
+	let window_id: usize = env::var("YABAI_WINDOW_ID")
+		.expect("YABAI_WINDOW_ID not found in environment.")
+		.parse()
+		.expect("Failed to parse window ID");
+	let windows = plugin.get_windows();
+	let window = some_or_return!(windows.iter().find(|w| w.id == window_id));
+	let space = some_or_return!(plugin
+		.get_spaces()
+		.into_iter()
+		.find(|space| space.index == window.space));
+	let space_key = get_space_key(&space, &plugin.get_displays());
+
+	// Windows that aren't part of the layout (e.g. floating windows) are never refocused
+	let mut layout = some_or_return!(plugin.read_layout(&space_key));
+	if !layout.contains_window(window.id) || layout.focused_window == Some(window.id) {
+		return;
+	}
+
+	// When the focused window is closed or minimized, macOS focuses another window before the
+	// trigger for the departed window runs. The departed window stays recorded as focused, so that
+	// the trigger focuses its neighbor instead.
+	if let Some(focused_window) = layout.focused_window {
+		if !windows
+			.iter()
+			.any(|w| w.id == focused_window && w.is_tiled())
+		{
+			log::debug!(
+				"The focused window {} left; keeping it recorded.",
+				focused_window
+			);
+			return;
+		}
+	}
+
+	layout.focused_window = Some(window.id);
+	plugin.write_layout(&space_key, layout);
+	log::debug!("Finished handling window_focused.");
+}
@@ -0,0 +1,7 @@
+use crate::context::{window::relayout_spaces_after_windows_left, YabaiPlugin};
+
+pub fn window_minimized(plugin: &YabaiPlugin) {
+	log::debug!("Starting to handle window_minimized.");
+	relayout_spaces_after_windows_left(plugin);
+	log::debug!("Finished handling window_minimized.");
+}
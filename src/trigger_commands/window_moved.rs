@@ -1,14 +1,48 @@
+use std::env;
+
 use crate::context::{
-	window::{create_windows_manager, UpdateWindowsProps},
+	window::{
+		create_windows_manager, create_windows_manager_for_window, relayout_other_visible_spaces,
+		GetWindowDataProps, UpdateWindowsProps,
+	},
 	YabaiPlugin,
 };
 
 pub fn window_moved(plugin: &YabaiPlugin) {
 	log::debug!("Starting to handle window_moved.");
+	let moved_window = env::var("YABAI_WINDOW_ID")
+		.ok()
+		.and_then(|window_id| window_id.parse().ok())
+		.and_then(|window_id| plugin.get_window(GetWindowDataProps::WindowId(window_id)));
+
+	// The plugin's own warps and swaps would otherwise be adopted as if the user had made them, even
+	// though the windows might only be halfway through being rearranged
+	if moved_window
+		.as_ref()
+		.is_some_and(|window| plugin.is_own_window_move(window))
+	{
+		log::debug!("The window was moved by the plugin itself; no changes were made.");
+		return;
+	}
+
 	let mut wm = create_windows_manager(plugin);
+	// The windows were moved around by the user, so their new order is the one to keep
+	wm.adopt_observed_window_order();
 	let state = plugin.read_state();
 	wm.update_windows(UpdateWindowsProps {
-		target_num_master_windows: state.num_master_windows[&wm.space.id],
+		target_num_master_windows: state.num_master_windows[&wm.space_key],
 	});
+	wm.save_layout();
+
+	// If the window was moved to another space, that space needs to be updated as well
+	if let Some(window) = moved_window {
+		if window.space != wm.space.index {
+			if let Some(mut window_wm) = create_windows_manager_for_window(plugin, &window) {
+				window_wm.relayout();
+			}
+		}
+	}
+
+	relayout_other_visible_spaces(plugin, &wm.space);
 	log::debug!("Finished handling window_moved.");
 }
@@ -1,12 +1,17 @@
 use crate::context::{
-	window::{create_windows_manager, UpdateWindowsProps},
+	window::{create_windows_manager, relayout_other_visible_spaces, UpdateWindowsProps},
 	YabaiPlugin,
 };
 
 pub fn yabai_started(plugin: &YabaiPlugin) {
+	// Spaces might have been created, destroyed or labelled since the state was last written
+	plugin.reconcile_state();
+
 	let mut wm = create_windows_manager(plugin);
 	let state = plugin.read_state();
 	wm.update_windows(UpdateWindowsProps {
-		target_num_master_windows: state.num_master_windows[&wm.space.id],
+		target_num_master_windows: state.num_master_windows[&wm.space_key],
 	});
+	wm.save_layout();
+	relayout_other_visible_spaces(plugin, &wm.space);
 }
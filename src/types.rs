@@ -1,6 +1,28 @@
-use serde::{Deserialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-#[derive(Clone, Deserialize)]
+/**
+ * yabai 4 renamed most of the window and space flags (e.g. `focused` became `has-focus`) and turned
+ * them from integers into booleans. The flags are kept as integers, and both schemas are accepted
+ * through aliases and this deserializer.
+ */
+fn deserialize_flag<'de, D>(deserializer: D) -> Result<usize, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	#[derive(Deserialize)]
+	#[serde(untagged)]
+	enum Flag {
+		Bool(bool),
+		Int(usize),
+	}
+
+	Ok(match Flag::deserialize(deserializer)? {
+		Flag::Bool(value) => value as usize,
+		Flag::Int(value) => value,
+	})
+}
+
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
 pub struct Frame {
 	pub x: f64,
 	pub y: f64,
@@ -19,25 +41,51 @@ pub struct Window {
 	pub level: usize,
 	pub role: String,
 	pub subrole: String,
+	#[serde(alias = "can-move", deserialize_with = "deserialize_flag")]
 	pub movable: usize,
+	#[serde(alias = "can-resize", deserialize_with = "deserialize_flag")]
 	pub resizable: usize,
 	pub display: usize,
 	pub space: usize,
+	#[serde(alias = "has-focus", deserialize_with = "deserialize_flag")]
 	pub focused: usize,
+	#[serde(alias = "split-type")]
 	pub split: String,
+	#[serde(alias = "is-floating", deserialize_with = "deserialize_flag")]
 	pub floating: usize,
+	#[serde(alias = "is-sticky", deserialize_with = "deserialize_flag")]
 	pub sticky: usize,
+	#[serde(alias = "is-minimized", deserialize_with = "deserialize_flag")]
 	pub minimized: usize,
+	/// Whether the window's application is hidden (yabai still lists its windows). Added in yabai 4.
+	#[serde(default, alias = "is-hidden", deserialize_with = "deserialize_flag")]
+	pub hidden: usize,
+	/// Removed in yabai 5
+	#[serde(default, alias = "is-topmost", deserialize_with = "deserialize_flag")]
 	pub topmost: usize,
 	pub opacity: f64,
+	#[serde(alias = "has-shadow", deserialize_with = "deserialize_flag")]
 	pub shadow: usize,
+	/// Removed in yabai 6
+	#[serde(default, alias = "has-border", deserialize_with = "deserialize_flag")]
 	pub border: usize,
 	pub stack_index: usize,
+	#[serde(alias = "has-parent-zoom", deserialize_with = "deserialize_flag")]
 	pub zoom_parent: usize,
+	#[serde(alias = "has-fullscreen-zoom", deserialize_with = "deserialize_flag")]
 	pub zoom_fullscreen: usize,
+	#[serde(alias = "is-native-fullscreen", deserialize_with = "deserialize_flag")]
 	pub native_fullscreen: usize,
 }
 
+impl Window {
+	/// Whether yabai tiles the window, which it doesn't do for floating and minimized windows or the
+	/// windows of hidden applications
+	pub fn is_tiled(&self) -> bool {
+		self.floating == 0 && self.minimized != 1 && self.hidden != 1
+	}
+}
+
 #[derive(Clone, Deserialize)]
 pub struct Display {
 	pub id: usize,
@@ -56,9 +104,105 @@ pub struct Space {
 	pub display: usize,
 	pub windows: Vec<usize>,
 	pub r#type: String,
+	#[serde(alias = "is-visible", deserialize_with = "deserialize_flag")]
 	pub visible: usize,
+	#[serde(alias = "has-focus", deserialize_with = "deserialize_flag")]
 	pub focused: usize,
+	#[serde(alias = "is-native-fullscreen", deserialize_with = "deserialize_flag")]
 	pub native_fullscreen: usize,
 	pub first_window: usize,
 	pub last_window: usize,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// The query output of every supported major version of yabai
+	const VERSIONS: [(&str, &str, &str, &str); 5] = [
+		(
+			"3",
+			include_str!("../fixtures/yabai/versions/v3/window.json"),
+			include_str!("../fixtures/yabai/versions/v3/space.json"),
+			include_str!("../fixtures/yabai/versions/v3/display.json"),
+		),
+		(
+			"4",
+			include_str!("../fixtures/yabai/versions/v4/window.json"),
+			include_str!("../fixtures/yabai/versions/v4/space.json"),
+			include_str!("../fixtures/yabai/versions/v4/display.json"),
+		),
+		(
+			"5",
+			include_str!("../fixtures/yabai/versions/v5/window.json"),
+			include_str!("../fixtures/yabai/versions/v5/space.json"),
+			include_str!("../fixtures/yabai/versions/v5/display.json"),
+		),
+		(
+			"6",
+			include_str!("../fixtures/yabai/versions/v6/window.json"),
+			include_str!("../fixtures/yabai/versions/v6/space.json"),
+			include_str!("../fixtures/yabai/versions/v6/display.json"),
+		),
+		(
+			"7",
+			include_str!("../fixtures/yabai/versions/v7/window.json"),
+			include_str!("../fixtures/yabai/versions/v7/space.json"),
+			include_str!("../fixtures/yabai/versions/v7/display.json"),
+		),
+	];
+
+	#[test]
+	fn parses_windows_of_every_yabai_version() {
+		for (version, window, _, _) in VERSIONS {
+			let window: Window = serde_json::from_str(window)
+				.unwrap_or_else(|e| panic!("Failed to parse yabai {} window: {}", version, e));
+
+			assert_eq!(window.id, 4262, "yabai {}", version);
+			assert_eq!(window.focused, 1, "yabai {}", version);
+			assert_eq!(window.floating, 0, "yabai {}", version);
+			assert_eq!(window.minimized, 0, "yabai {}", version);
+			assert_eq!(window.hidden, 0, "yabai {}", version);
+			assert!(window.is_tiled(), "yabai {}", version);
+			assert_eq!(window.movable, 1, "yabai {}", version);
+			assert_eq!(window.split, "vertical", "yabai {}", version);
+			assert_eq!(window.zoom_parent, 0, "yabai {}", version);
+			assert_eq!(window.frame.w, 720.0, "yabai {}", version);
+		}
+	}
+
+	#[test]
+	fn windows_of_hidden_applications_are_not_tiled() {
+		let window = include_str!("../fixtures/yabai/versions/v7/window.json")
+			.replace("\"is-hidden\":false", "\"is-hidden\":true");
+		let window: Window = serde_json::from_str(&window).unwrap();
+
+		assert_eq!(window.hidden, 1);
+		assert!(!window.is_tiled());
+	}
+
+	#[test]
+	fn parses_spaces_of_every_yabai_version() {
+		for (version, _, space, _) in VERSIONS {
+			let space: Space = serde_json::from_str(space)
+				.unwrap_or_else(|e| panic!("Failed to parse yabai {} space: {}", version, e));
+
+			assert_eq!(space.label, "code", "yabai {}", version);
+			assert_eq!(space.focused, 1, "yabai {}", version);
+			assert_eq!(space.visible, 1, "yabai {}", version);
+			assert_eq!(space.native_fullscreen, 0, "yabai {}", version);
+			assert_eq!(space.windows, [4262, 4270], "yabai {}", version);
+		}
+	}
+
+	#[test]
+	fn parses_displays_of_every_yabai_version() {
+		for (version, _, _, display) in VERSIONS {
+			let display: Display = serde_json::from_str(display)
+				.unwrap_or_else(|e| panic!("Failed to parse yabai {} display: {}", version, e));
+
+			assert_eq!(display.index, 1, "yabai {}", version);
+			assert_eq!(display.spaces, [1, 2], "yabai {}", version);
+		}
+	}
+}
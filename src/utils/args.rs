@@ -0,0 +1,64 @@
+/**
+ * Returns whether a flag without a value (e.g. `--json`) was passed
+ */
+pub fn has_flag(args: &[String], flag: &str) -> bool {
+	args.iter().any(|arg| arg == flag)
+}
+
+/**
+ * Returns the value that follows an option (e.g. `--space 2`)
+ */
+pub fn get_option_value<'a>(args: &'a [String], option: &str) -> Option<&'a str> {
+	args.iter()
+		.position(|arg| arg == option)
+		.and_then(|position| args.get(position + 1))
+		.map(|value| value.as_str())
+}
+
+/**
+ * Returns the arguments that aren't flags, options or the values of the given options
+ */
+pub fn get_positional_args<'a>(args: &'a [String], options_with_values: &[&str]) -> Vec<&'a str> {
+	let mut positional_args = vec![];
+	let mut args_iter = args.iter();
+	while let Some(arg) = args_iter.next() {
+		if options_with_values.contains(&arg.as_str()) {
+			args_iter.next();
+		} else if !arg.starts_with("--") {
+			positional_args.push(arg.as_str());
+		}
+	}
+
+	positional_args
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn args(args: &[&str]) -> Vec<String> {
+		args.iter().map(|arg| arg.to_string()).collect()
+	}
+
+	#[test]
+	fn parses_flags_options_and_positional_args() {
+		let args = args(&["master-count", "--space", "code", "3", "--json"]);
+
+		assert!(has_flag(&args, "--json"));
+		assert!(!has_flag(&args, "--force"));
+		assert_eq!(get_option_value(&args, "--space"), Some("code"));
+		assert_eq!(get_option_value(&args, "--count"), None);
+		assert_eq!(
+			get_positional_args(&args, &["--space"]),
+			vec!["master-count", "3"]
+		);
+	}
+
+	#[test]
+	fn handles_options_without_values() {
+		let args = args(&["--space"]);
+
+		assert_eq!(get_option_value(&args, "--space"), None);
+		assert!(get_positional_args(&args, &["--space"]).is_empty());
+	}
+}
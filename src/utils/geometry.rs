@@ -0,0 +1,123 @@
+use crate::types::Frame;
+
+/**
+ * The largest difference (in points) between two coordinates that are considered to be the same.
+ * yabai reports fractional coordinates on Retina and scaled displays, so windows that are aligned
+ * can be off from each other by fractions of a point. The tolerance is a whole point to stay clear
+ * of those offsets, which is still far below the distance between any two columns of windows.
+ */
+pub const EPSILON: f64 = 1.0;
+
+pub fn approx_eq(a: f64, b: f64) -> bool {
+	(a - b).abs() <= EPSILON
+}
+
+/**
+ * Returns whether `a` is less than `b` by more than `EPSILON`
+ */
+pub fn approx_lt(a: f64, b: f64) -> bool {
+	a < b - EPSILON
+}
+
+/**
+ * Returns whether `a` is less than `b` or approximately equal to it
+ */
+pub fn approx_le(a: f64, b: f64) -> bool {
+	a <= b + EPSILON
+}
+
+/**
+ * Returns whether `a` is greater than `b` by more than `EPSILON`
+ */
+pub fn approx_gt(a: f64, b: f64) -> bool {
+	approx_lt(b, a)
+}
+
+/**
+ * Returns whether `a` is greater than `b` or approximately equal to it
+ */
+pub fn approx_ge(a: f64, b: f64) -> bool {
+	approx_le(b, a)
+}
+
+/**
+ * Rounds a coordinate to a multiple of `EPSILON`, for sorting by coordinates that should compare as
+ * equal when they are close. Unlike `approx_eq`, this is transitive (so it can be used as a sort
+ * key), but two close coordinates can still end up on either side of a rounding boundary.
+ */
+pub fn snap_to_grid(value: f64) -> i64 {
+	(value / EPSILON).round() as i64
+}
+
+impl Frame {
+	pub fn from_edges(left: f64, top: f64, right: f64, bottom: f64) -> Self {
+		Frame {
+			x: left,
+			y: top,
+			w: right - left,
+			h: bottom - top,
+		}
+	}
+
+	pub fn left(&self) -> f64 {
+		self.x
+	}
+
+	pub fn top(&self) -> f64 {
+		self.y
+	}
+
+	pub fn right(&self) -> f64 {
+		self.x + self.w
+	}
+
+	pub fn bottom(&self) -> f64 {
+		self.y + self.h
+	}
+
+	/// Whether both frames have the same position and size, up to `EPSILON` in each coordinate
+	pub fn approx_eq(&self, other: &Frame) -> bool {
+		approx_eq(self.x, other.x)
+			&& approx_eq(self.y, other.y)
+			&& approx_eq(self.w, other.w)
+			&& approx_eq(self.h, other.h)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn compares_coordinates_with_a_tolerance() {
+		assert!(approx_eq(720.0, 720.4));
+		assert!(!approx_eq(720.0, 721.5));
+		assert!(!approx_lt(719.5, 720.0));
+		assert!(approx_lt(718.5, 720.0));
+		assert!(approx_le(720.5, 720.0));
+		assert!(approx_gt(721.5, 720.0));
+		assert!(approx_ge(719.5, 720.0));
+	}
+
+	#[test]
+	fn snaps_coordinates_to_the_grid() {
+		assert_eq!(snap_to_grid(719.6), snap_to_grid(720.4));
+		assert!(snap_to_grid(0.0) < snap_to_grid(720.0));
+	}
+
+	#[test]
+	fn converts_between_edges_and_frames() {
+		let frame = Frame::from_edges(10.0, 25.0, 1430.0, 890.0);
+		assert!(frame.approx_eq(&Frame {
+			x: 10.0,
+			y: 25.0,
+			w: 1420.0,
+			h: 865.0,
+		}));
+		assert_eq!(frame.left(), 10.0);
+		assert_eq!(frame.top(), 25.0);
+		assert_eq!(frame.right(), 1430.0);
+		assert_eq!(frame.bottom(), 890.0);
+		assert!(!frame.approx_eq(&Frame::from_edges(10.0, 25.0, 1430.0, 900.0)));
+	}
+}
@@ -1 +1,4 @@
-pub mod lock;
\ No newline at end of file
+pub mod args;
+pub mod geometry;
+pub mod lock;
+pub mod rotating_file;
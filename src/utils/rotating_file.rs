@@ -0,0 +1,190 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+	fs::{self, File, OpenOptions},
+	io::Write,
+	time::Duration,
+};
+
+use super::lock::LockManager;
+
+/// A rotation lock older than this was left behind by an invocation that crashed while rotating
+const STALE_ROTATION_LOCK_AGE: Duration = Duration::from_secs(60);
+
+/**
+ * A file that is only ever appended to (the log, the metrics and the journal), which is rotated
+ * once it grows past a maximum size: the file becomes the first rotated file, every rotated file
+ * moves one place back and the oldest one is dropped
+ */
+pub struct RotatingFile {
+	pub path: String,
+	/// The rotated files, newest first
+	pub rotated_paths: Vec<String>,
+}
+
+impl RotatingFile {
+	pub fn new(path: String, rotated_paths: Vec<String>) -> Self {
+		Self {
+			path,
+			rotated_paths,
+		}
+	}
+
+	fn is_larger_than(&self, max_size: u64) -> bool {
+		fs::metadata(&self.path).is_ok_and(|metadata| metadata.len() > max_size)
+	}
+
+	/// Rotates the file if it is larger than `max_size` bytes. Invocations append to these files
+	/// without holding the plugin lock (e.g. from the panic hook or before the lock is acquired), so
+	/// the rotation has a lock of its own: an invocation that finds another one rotating skips
+	/// rotating (otherwise both would shift the same files and drop a rotated file).
+	pub fn rotate_if_larger_than(&self, max_size: u64) {
+		if !self.is_larger_than(max_size) {
+			return;
+		}
+
+		let mut rotation_lock = LockManager::new(format!("{}.rotation.lock", self.path));
+		if rotation_lock.acquire_lock().is_err() {
+			let is_stale = fs::metadata(&rotation_lock.lock_path)
+				.and_then(|metadata| metadata.modified())
+				.is_ok_and(|modified| {
+					modified.elapsed().unwrap_or_default() > STALE_ROTATION_LOCK_AGE
+				});
+			if !is_stale || rotation_lock.release_lock(true).is_err() {
+				return;
+			}
+			if rotation_lock.acquire_lock().is_err() {
+				return;
+			}
+		}
+
+		// Another invocation might have finished rotating between the size check and acquiring the
+		// lock
+		if self.is_larger_than(max_size) {
+			for i in (1..self.rotated_paths.len()).rev() {
+				fs::rename(&self.rotated_paths[i - 1], &self.rotated_paths[i]).ok();
+			}
+			if let Some(newest_rotated_path) = self.rotated_paths.first() {
+				fs::rename(&self.path, newest_rotated_path).ok();
+			} else {
+				fs::remove_file(&self.path).ok();
+			}
+		}
+		rotation_lock.release_lock(false).ok();
+	}
+
+	/// Rotates the file if it is larger than `max_size` bytes and opens it for appending
+	pub fn open_for_appending(&self, max_size: u64) -> std::io::Result<File> {
+		self.rotate_if_larger_than(max_size);
+		OpenOptions::new()
+			.create(true)
+			.append(true)
+			.open(&self.path)
+	}
+
+	/// Appends a value as one line of JSON. The line is written with a single write, so lines that
+	/// concurrent invocations append don't interleave.
+	pub fn append_json_line(&self, value: &impl Serialize, max_size: u64) -> anyhow::Result<()> {
+		let mut file = self.open_for_appending(max_size)?;
+		file.write_all(format!("{}\n", serde_json::to_string(value)?).as_bytes())?;
+		Ok(())
+	}
+
+	/// Reads the lines of the rotated files and the file, oldest first. Lines that can't be parsed
+	/// (e.g. because of a crash while writing) are skipped.
+	pub fn read_json_lines<T: DeserializeOwned>(&self) -> Vec<T> {
+		self.rotated_paths
+			.iter()
+			.rev()
+			.chain([&self.path])
+			.filter_map(|path| fs::read_to_string(path).ok())
+			.flat_map(|contents| {
+				contents
+					.lines()
+					.filter_map(|line| serde_json::from_str(line).ok())
+					.collect::<Vec<T>>()
+			})
+			.collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn create_rotating_file(name: &str, num_rotated_files: usize) -> RotatingFile {
+		let dir = std::env::temp_dir().join(format!(
+			"yabai-rotating-file-test-{}-{}",
+			name,
+			std::process::id()
+		));
+		fs::remove_dir_all(&dir).ok();
+		fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("file.jsonl").to_string_lossy().to_string();
+		let rotated_paths = (1..=num_rotated_files)
+			.map(|i| format!("{}.{}", path, i))
+			.collect();
+		RotatingFile::new(path, rotated_paths)
+	}
+
+	fn remove_rotating_file(file: &RotatingFile) {
+		let dir = std::path::Path::new(&file.path).parent().unwrap();
+		fs::remove_dir_all(dir).ok();
+	}
+
+	#[test]
+	fn keeps_the_newest_rotated_files_and_reads_them_oldest_first() {
+		let file = create_rotating_file("rotation", 2);
+		let line_size = "1\n".len() as u64;
+
+		// The file only rotates once it is past the max size, so each file holds two lines
+		for value in 1..=7 {
+			file.append_json_line(&value, line_size).unwrap();
+		}
+
+		// The oldest lines were dropped along with the oldest rotated file
+		assert_eq!(file.read_json_lines::<u32>(), [3, 4, 5, 6, 7]);
+		assert_eq!(fs::read_to_string(&file.path).unwrap(), "7\n");
+		assert_eq!(
+			fs::read_to_string(&file.rotated_paths[1]).unwrap(),
+			"3\n4\n"
+		);
+
+		remove_rotating_file(&file);
+	}
+
+	#[test]
+	fn skips_partial_lines() {
+		let file = create_rotating_file("partial", 1);
+
+		fs::write(&file.rotated_paths[0], "1\n{\"a\n").unwrap();
+		fs::write(&file.path, "2\n").unwrap();
+
+		assert_eq!(file.read_json_lines::<u32>(), [1, 2]);
+		let empty_file = create_rotating_file("empty", 1);
+		assert!(empty_file.read_json_lines::<u32>().is_empty());
+
+		remove_rotating_file(&file);
+		remove_rotating_file(&empty_file);
+	}
+
+	#[test]
+	fn skips_rotating_while_another_invocation_is_rotating() {
+		let file = create_rotating_file("lock", 3);
+		let big_file = vec![b'a'; 1024];
+
+		fs::write(&file.path, &big_file).unwrap();
+		let mut rotation_lock = LockManager::new(format!("{}.rotation.lock", file.path));
+		rotation_lock.acquire_lock().unwrap();
+		file.rotate_if_larger_than(1000);
+		assert!(fs::metadata(&file.path).is_ok());
+		assert!(fs::metadata(&file.rotated_paths[0]).is_err());
+
+		rotation_lock.release_lock(false).unwrap();
+		file.rotate_if_larger_than(1000);
+		assert!(fs::metadata(&file.path).is_err());
+		assert_eq!(fs::read(&file.rotated_paths[0]).unwrap(), big_file);
+		assert!(fs::metadata(&rotation_lock.lock_path).is_err());
+
+		remove_rotating_file(&file);
+	}
+}